@@ -3,38 +3,343 @@ use crate::parser::BinaryExpression;
 use crate::raw_cstr;
 use llvm_sys::core::*;
 use llvm_sys::prelude::*;
+use llvm_sys::{LLVMIntPredicate, LLVMRealPredicate};
 
 pub fn compile_binary_expression_to_instruction(
     containing_block: &super::CodegenBlock,
     expression: &BinaryExpression,
 ) -> LLVMValueRef {
-    let right = super::compile_expression_to_instruction(containing_block, &expression.right);
+    // left to right, like sema, a `let` or assignment on the left is visible
+    // on the right
     let left = super::compile_expression_to_instruction(containing_block, &expression.left);
+    if containing_block.is_terminated() {
+        return super::never_value();
+    }
+
+    let right = super::compile_expression_to_instruction(containing_block, &expression.right);
+    if containing_block.is_terminated() {
+        return super::never_value();
+    }
+
+    // sema guarantees both operands have the same type, which is also the type
+    // of the expression unless it is a comparison
+    let is_float = expression.ty.is_float();
+    let is_signed = expression.ty.is_signed_integer();
 
     match expression.operator {
-        BinaryOperator::Add => unsafe {
-            LLVMBuildAdd(
+        operator if operator.is_comparison() => {
+            compile_comparison(containing_block, expression, left, right)
+        }
+        BinaryOperator::Add if is_float => unsafe {
+            LLVMBuildFAdd(
                 containing_block.builder,
                 left,
                 right,
                 raw_cstr("__ijssel_tmp"),
             )
         },
-        BinaryOperator::Mul => unsafe {
-            LLVMBuildMul(
+        BinaryOperator::Mul if is_float => unsafe {
+            LLVMBuildFMul(
                 containing_block.builder,
                 left,
                 right,
                 raw_cstr("__ijssel_tmp"),
             )
         },
-        BinaryOperator::Sub => unsafe {
-            LLVMBuildSub(
+        BinaryOperator::Sub if is_float => unsafe {
+            LLVMBuildFSub(
                 containing_block.builder,
                 left,
                 right,
                 raw_cstr("__ijssel_tmp"),
             )
         },
+        BinaryOperator::Add | BinaryOperator::Sub | BinaryOperator::Mul => {
+            compile_integer_arithmetic(containing_block, expression, left, right)
+        }
+        BinaryOperator::Div if is_float => unsafe {
+            LLVMBuildFDiv(
+                containing_block.builder,
+                left,
+                right,
+                raw_cstr("__ijssel_tmp"),
+            )
+        },
+        BinaryOperator::Div if is_signed => {
+            check_division(containing_block, expression, left, right);
+            unsafe {
+                LLVMBuildSDiv(
+                    containing_block.builder,
+                    left,
+                    right,
+                    raw_cstr("__ijssel_tmp"),
+                )
+            }
+        }
+        BinaryOperator::Div => {
+            check_division(containing_block, expression, left, right);
+            unsafe {
+                LLVMBuildUDiv(
+                    containing_block.builder,
+                    left,
+                    right,
+                    raw_cstr("__ijssel_tmp"),
+                )
+            }
+        }
+        BinaryOperator::Rem if is_float => unsafe {
+            LLVMBuildFRem(
+                containing_block.builder,
+                left,
+                right,
+                raw_cstr("__ijssel_tmp"),
+            )
+        },
+        BinaryOperator::Rem if is_signed => {
+            check_division(containing_block, expression, left, right);
+            unsafe {
+                LLVMBuildSRem(
+                    containing_block.builder,
+                    left,
+                    right,
+                    raw_cstr("__ijssel_tmp"),
+                )
+            }
+        }
+        BinaryOperator::Rem => {
+            check_division(containing_block, expression, left, right);
+            unsafe {
+                LLVMBuildURem(
+                    containing_block.builder,
+                    left,
+                    right,
+                    raw_cstr("__ijssel_tmp"),
+                )
+            }
+        }
+        BinaryOperator::BitAnd => unsafe {
+            LLVMBuildAnd(
+                containing_block.builder,
+                left,
+                right,
+                raw_cstr("__ijssel_tmp"),
+            )
+        },
+        BinaryOperator::BitOr => unsafe {
+            LLVMBuildOr(
+                containing_block.builder,
+                left,
+                right,
+                raw_cstr("__ijssel_tmp"),
+            )
+        },
+        BinaryOperator::BitXor => unsafe {
+            LLVMBuildXor(
+                containing_block.builder,
+                left,
+                right,
+                raw_cstr("__ijssel_tmp"),
+            )
+        },
+        BinaryOperator::Shl | BinaryOperator::Shr => {
+            compile_shift(containing_block, expression, left, right)
+        }
+        operator => unreachable!("{} is not an arithmetic operator", operator),
     }
 }
+
+/// Integer arithmetic wraps around on overflow, unless it is checked, in
+/// which case it panics.
+fn compile_integer_arithmetic(
+    containing_block: &super::CodegenBlock,
+    expression: &BinaryExpression,
+    left: LLVMValueRef,
+    right: LLVMValueRef,
+) -> LLVMValueRef {
+    let builder = containing_block.builder;
+
+    if containing_block.checked_arithmetic {
+        let (operation, message) = match expression.operator {
+            BinaryOperator::Add => ("add", "attempt to add with overflow"),
+            BinaryOperator::Sub => ("sub", "attempt to subtract with overflow"),
+            _ => ("mul", "attempt to multiply with overflow"),
+        };
+
+        return super::intrinsics::build_checked_arithmetic(
+            containing_block,
+            operation,
+            expression.ty.is_signed_integer(),
+            left,
+            right,
+            message,
+            expression.span,
+        );
+    }
+
+    unsafe {
+        match expression.operator {
+            BinaryOperator::Add => LLVMBuildAdd(builder, left, right, raw_cstr("__ijssel_tmp")),
+            BinaryOperator::Sub => LLVMBuildSub(builder, left, right, raw_cstr("__ijssel_tmp")),
+            _ => LLVMBuildMul(builder, left, right, raw_cstr("__ijssel_tmp")),
+        }
+    }
+}
+
+/// The shift amount is converted to the type of the value being shifted and
+/// only its lowest bits are used, so that shifting by the width of the type
+/// or more wraps around instead of being undefined. A right shift of a
+/// signed integer copies the sign bit.
+fn compile_shift(
+    containing_block: &super::CodegenBlock,
+    expression: &BinaryExpression,
+    left: LLVMValueRef,
+    right: LLVMValueRef,
+) -> LLVMValueRef {
+    let builder = containing_block.builder;
+    let ty = unsafe { LLVMTypeOf(left) };
+    let width = expression.ty.integer_width().unwrap();
+
+    unsafe {
+        let amount = LLVMBuildIntCast2(builder, right, ty, 0, raw_cstr("__ijssel_tmp"));
+        let amount = LLVMBuildAnd(
+            builder,
+            amount,
+            LLVMConstInt(ty, (width - 1) as u64, 0),
+            raw_cstr("__ijssel_tmp"),
+        );
+
+        match expression.operator {
+            BinaryOperator::Shl => LLVMBuildShl(builder, left, amount, raw_cstr("__ijssel_tmp")),
+            _ if expression.ty.is_signed_integer() => {
+                LLVMBuildAShr(builder, left, amount, raw_cstr("__ijssel_tmp"))
+            }
+            _ => LLVMBuildLShr(builder, left, amount, raw_cstr("__ijssel_tmp")),
+        }
+    }
+}
+
+/// Integers compare according to their signedness, `false` is less than
+/// `true` and floats compare ordered, except `!=` which is true when either
+/// side is NaN.
+fn compile_comparison(
+    containing_block: &super::CodegenBlock,
+    expression: &BinaryExpression,
+    left: LLVMValueRef,
+    right: LLVMValueRef,
+) -> LLVMValueRef {
+    use BinaryOperator::*;
+
+    let operand_type = expression.left.ty();
+
+    if operand_type.is_float() {
+        let predicate = match expression.operator {
+            Lt => LLVMRealPredicate::LLVMRealOLT,
+            Gt => LLVMRealPredicate::LLVMRealOGT,
+            Le => LLVMRealPredicate::LLVMRealOLE,
+            Ge => LLVMRealPredicate::LLVMRealOGE,
+            Eq => LLVMRealPredicate::LLVMRealOEQ,
+            Ne => LLVMRealPredicate::LLVMRealUNE,
+            operator => unreachable!("{} is not a comparison", operator),
+        };
+
+        return unsafe {
+            LLVMBuildFCmp(
+                containing_block.builder,
+                predicate,
+                left,
+                right,
+                raw_cstr("__ijssel_tmp"),
+            )
+        };
+    }
+
+    let signed = operand_type.is_signed_integer();
+    let predicate = match expression.operator {
+        Lt if signed => LLVMIntPredicate::LLVMIntSLT,
+        Gt if signed => LLVMIntPredicate::LLVMIntSGT,
+        Le if signed => LLVMIntPredicate::LLVMIntSLE,
+        Ge if signed => LLVMIntPredicate::LLVMIntSGE,
+        Lt => LLVMIntPredicate::LLVMIntULT,
+        Gt => LLVMIntPredicate::LLVMIntUGT,
+        Le => LLVMIntPredicate::LLVMIntULE,
+        Ge => LLVMIntPredicate::LLVMIntUGE,
+        Eq => LLVMIntPredicate::LLVMIntEQ,
+        Ne => LLVMIntPredicate::LLVMIntNE,
+        operator => unreachable!("{} is not a comparison", operator),
+    };
+
+    unsafe {
+        LLVMBuildICmp(
+            containing_block.builder,
+            predicate,
+            left,
+            right,
+            raw_cstr("__ijssel_tmp"),
+        )
+    }
+}
+
+/// Integer division by zero, and of the minimum value of a signed type by -1,
+/// is undefined in LLVM. Both panic at runtime instead, float division
+/// follows IEEE 754.
+fn check_division(
+    containing_block: &super::CodegenBlock,
+    expression: &BinaryExpression,
+    left: LLVMValueRef,
+    right: LLVMValueRef,
+) {
+    let (zero_message, overflow_message) = match expression.operator {
+        BinaryOperator::Rem => (
+            "attempt to calculate the remainder with a divisor of zero",
+            "attempt to calculate the remainder with overflow",
+        ),
+        _ => (
+            "attempt to divide by zero",
+            "attempt to divide with overflow",
+        ),
+    };
+
+    let ty = unsafe { LLVMTypeOf(right) };
+    let (minimum, _) = expression.ty.integer_range().unwrap();
+    let builder = containing_block.builder;
+
+    let is_zero = unsafe {
+        LLVMBuildICmp(
+            builder,
+            LLVMIntPredicate::LLVMIntEQ,
+            right,
+            LLVMConstNull(ty),
+            raw_cstr("__ijssel_tmp"),
+        )
+    };
+    super::runtime::build_panic_if(containing_block, is_zero, zero_message, expression.span);
+
+    if !expression.ty.is_signed_integer() {
+        return;
+    }
+
+    let overflows = unsafe {
+        let is_minimum = LLVMBuildICmp(
+            builder,
+            LLVMIntPredicate::LLVMIntEQ,
+            left,
+            LLVMConstInt(ty, minimum as u64, 1),
+            raw_cstr("__ijssel_tmp"),
+        );
+        let is_minus_one = LLVMBuildICmp(
+            builder,
+            LLVMIntPredicate::LLVMIntEQ,
+            right,
+            LLVMConstAllOnes(ty),
+            raw_cstr("__ijssel_tmp"),
+        );
+
+        LLVMBuildAnd(builder, is_minimum, is_minus_one, raw_cstr("__ijssel_tmp"))
+    };
+    super::runtime::build_panic_if(
+        containing_block,
+        overflows,
+        overflow_message,
+        expression.span,
+    );
+}
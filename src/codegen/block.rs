@@ -1,24 +1,53 @@
-use crate::parser::BlockExpression;
+use crate::parser::{BlockExpression, Type};
 use crate::raw_cstr;
 use llvm_sys::core::*;
 use llvm_sys::prelude::*;
 
+use std::cell::RefCell;
 use std::collections::hash_map::HashMap;
 
-pub type BindingsHashMap = HashMap<String, LLVMValueRef>;
+/// A local variable. Parameters and `let` bindings are plain values, `var`
+/// bindings live in a stack slot so that they can be assigned to.
+#[derive(Clone, Copy)]
+pub enum Binding {
+    Value(LLVMValueRef),
+    Slot {
+        pointer: LLVMValueRef,
+        ty: LLVMTypeRef,
+    },
+}
+
+pub type BindingsHashMap = HashMap<String, Binding>;
+
+/// Where `break` and `continue` jump to in a loop.
+#[derive(Clone, Copy)]
+pub struct LoopTarget {
+    pub break_block: LLVMBasicBlockRef,
+    pub continue_block: LLVMBasicBlockRef,
+}
 
 pub struct CodegenBlock {
-    pub bindings: BindingsHashMap,
+    /// The variables in scope, one map per block, innermost last.
+    pub scopes: RefCell<Vec<BindingsHashMap>>,
     pub module: LLVMModuleRef,
-    pub inner: LLVMBasicBlockRef,
+    pub function: LLVMValueRef,
+    /// The return type of `function`.
+    pub return_type: Type,
     pub builder: LLVMBuilderRef,
+    /// The loops around the expression being compiled, innermost last.
+    pub loops: RefCell<Vec<LoopTarget>>,
+    /// Whether integer arithmetic panics on overflow instead of wrapping.
+    pub checked_arithmetic: bool,
 }
 
 impl CodegenBlock {
     pub fn new(
         module: LLVMModuleRef,
+        function: LLVMValueRef,
+        return_type: Type,
         inner: LLVMBasicBlockRef,
         bindings: BindingsHashMap,
+        checked_arithmetic: bool,
     ) -> CodegenBlock {
         let builder = unsafe { LLVMCreateBuilder() };
         unsafe {
@@ -26,38 +55,134 @@ impl CodegenBlock {
         }
 
         CodegenBlock {
-            bindings,
+            scopes: RefCell::new(vec![bindings]),
             module,
+            function,
+            return_type,
             builder,
-            inner,
+            loops: RefCell::new(Vec::new()),
+            checked_arithmetic,
+        }
+    }
+
+    /// Appends a new basic block to the function being compiled, without
+    /// moving the builder to it.
+    pub fn append_basic_block(&self, name: &str) -> LLVMBasicBlockRef {
+        unsafe { LLVMAppendBasicBlock(self.function, raw_cstr(name)) }
+    }
+
+    pub fn position_at_end(&self, basic_block: LLVMBasicBlockRef) {
+        unsafe { LLVMPositionBuilderAtEnd(self.builder, basic_block) }
+    }
+
+    /// The basic block instructions are currently added to.
+    pub fn current_basic_block(&self) -> LLVMBasicBlockRef {
+        unsafe { LLVMGetInsertBlock(self.builder) }
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<Binding> {
+        self.scopes
+            .borrow()
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Adds a binding to the innermost scope, shadowing any earlier one.
+    pub fn declare(&self, name: &str, binding: Binding) {
+        self.scopes
+            .borrow_mut()
+            .last_mut()
+            .unwrap()
+            .insert(name.to_string(), binding);
+    }
+
+    /// Allocates a stack slot at the start of the entry block, where LLVM's
+    /// mem2reg pass expects them, regardless of where the builder is.
+    pub fn build_entry_alloca(&self, ty: LLVMTypeRef, name: &str) -> LLVMValueRef {
+        unsafe {
+            let entry = LLVMGetEntryBasicBlock(self.function);
+            let builder = LLVMCreateBuilder();
+
+            let first_instruction = LLVMGetFirstInstruction(entry);
+            if first_instruction.is_null() {
+                LLVMPositionBuilderAtEnd(builder, entry);
+            } else {
+                LLVMPositionBuilderBefore(builder, first_instruction);
+            }
+
+            let pointer = LLVMBuildAlloca(builder, ty, raw_cstr(name));
+            LLVMDisposeBuilder(builder);
+            pointer
+        }
+    }
+
+    /// Whether the current basic block already ends in a branch or return,
+    /// after which no more instructions can be added to it.
+    pub fn is_terminated(&self) -> bool {
+        unsafe { !LLVMGetBasicBlockTerminator(self.current_basic_block()).is_null() }
+    }
+
+    /// Returns `value` from the function, which is ignored when the function
+    /// returns `void`.
+    pub fn build_return(&self, value: LLVMValueRef) {
+        unsafe {
+            if self.return_type == Type::Void {
+                LLVMBuildRetVoid(self.builder);
+            } else {
+                LLVMBuildRet(self.builder, value);
+            }
         }
     }
 }
 
 pub fn compile_block(
     module: &LLVMModuleRef,
+    function: LLVMValueRef,
     ast_block: &BlockExpression,
+    return_type: Type,
     inherited_bindings: BindingsHashMap,
-) -> LLVMBasicBlockRef {
-    let context = unsafe { LLVMGetModuleContext(*module) };
-    let basic_block = unsafe { LLVMCreateBasicBlockInContext(context, raw_cstr("entry")) };
-    let block = CodegenBlock::new(*module, basic_block, inherited_bindings.clone());
+    checked_arithmetic: bool,
+) {
+    let basic_block = unsafe { LLVMAppendBasicBlock(function, raw_cstr("entry")) };
+    let block = CodegenBlock::new(
+        *module,
+        function,
+        return_type,
+        basic_block,
+        inherited_bindings,
+        checked_arithmetic,
+    );
 
-    let mut last_value_ref = None;
-    for expression in &ast_block.expressions {
-        last_value_ref = Some(super::compile_expression_to_instruction(&block, expression));
+    // always return last expression result, an empty body returns zero
+    let return_value = compile_expressions(&block, ast_block)
+        .unwrap_or_else(|| unsafe { LLVMConstInt(LLVMInt64Type(), 0, 0) });
+
+    // the body can end in a `return` of its own
+    if !block.is_terminated() {
+        block.build_return(return_value);
     }
+}
 
-    // always return last expression result
-    if let Some(last_value_ref) = last_value_ref {
-        unsafe {
-            LLVMBuildRet(block.builder, last_value_ref);
-        }
-    } else {
-        unsafe {
-            LLVMBuildRetVoid(block.builder);
+/// Compiles the expressions of a block at the current position of the
+/// builder in a new scope, returning the value of the last one. Expressions
+/// after one that terminates the current basic block can never run and are
+/// skipped, the caller can tell by `CodegenBlock::is_terminated`.
+pub fn compile_expressions(
+    block: &CodegenBlock,
+    ast_block: &BlockExpression,
+) -> Option<LLVMValueRef> {
+    let mut last_value_ref = None;
+
+    block.scopes.borrow_mut().push(HashMap::new());
+    for expression in &ast_block.expressions {
+        if block.is_terminated() {
+            break;
         }
+
+        last_value_ref = Some(super::compile_expression_to_instruction(block, expression));
     }
+    block.scopes.borrow_mut().pop();
 
-    return basic_block;
+    last_value_ref
 }
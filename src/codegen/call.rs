@@ -1,44 +1,93 @@
 use llvm_sys::core::*;
 use llvm_sys::prelude::*;
 
-use crate::parser::CallExpression;
+use super::types::llvm_type;
+use crate::parser::{CallExpression, Type};
 use crate::raw_cstr;
+use crate::sema::Builtin;
 
 pub fn compile_call(block: &super::CodegenBlock, expression: &CallExpression) -> LLVMValueRef {
     let name = expression.function_name.as_str();
-    let callee = unsafe { LLVMGetNamedFunction(block.module, raw_cstr(name)) };
-    if callee.is_null() {
-        panic!("Function {} not found", name)
+
+    if let Some(builtin) = Builtin::from_name(name) {
+        return compile_builtin_call(block, builtin, expression);
     }
 
-    let expected_args_size = unsafe { LLVMCountParams(callee) } as usize;
-    if expected_args_size != expression.args.len() {
+    let callee = unsafe { LLVMGetNamedFunction(block.module, raw_cstr(name)) };
+    if callee.is_null() {
         panic!(
-            "Expected {} args for function {}, got {}",
-            expected_args_size,
-            name,
-            expression.args.len()
+            "Function {} was not declared, sema should have caught this",
+            name
         )
     }
 
-    let mut args: Vec<LLVMValueRef> = Vec::with_capacity(expected_args_size);
+    let mut args: Vec<LLVMValueRef> = Vec::with_capacity(expression.args.len());
     let arg_list: *mut LLVMValueRef = {
         for expression in &expression.args {
             args.push(super::compile_expression_to_instruction(block, expression));
+            if block.is_terminated() {
+                return super::never_value();
+            }
         }
 
         args.as_mut_ptr()
     };
 
-    let call_value_ref = unsafe {
+    // LLVM does not allow naming the result of a call that returns nothing
+    let name = if expression.ty == Type::Void {
+        ""
+    } else {
+        "__ijssel_tmp"
+    };
+
+    unsafe {
         LLVMBuildCall(
             block.builder,
             callee,
             arg_list,
             args.len() as u32,
-            raw_cstr("__ijssel_tmp"),
+            raw_cstr(name),
         )
-    };
+    }
+}
+
+fn compile_builtin_call(
+    block: &super::CodegenBlock,
+    builtin: Builtin,
+    expression: &CallExpression,
+) -> LLVMValueRef {
+    let mut args: Vec<LLVMValueRef> = Vec::with_capacity(expression.args.len());
+    for arg in &expression.args {
+        args.push(super::compile_expression_to_instruction(block, arg));
+        if block.is_terminated() {
+            return super::never_value();
+        }
+    }
+
+    let destination_type = llvm_type(expression.ty);
 
-    call_value_ref
+    match builtin {
+        Builtin::ToFloat => unsafe {
+            LLVMBuildSIToFP(
+                block.builder,
+                args[0],
+                destination_type,
+                raw_cstr("__ijssel_tmp"),
+            )
+        },
+        // saturates like `as`, instead of giving poison for NaN and values
+        // out of range
+        Builtin::ToInt => {
+            super::cast::build_float_to_integer(block, args[0], Type::F64, expression.ty)
+        }
+        Builtin::WrappingAdd => unsafe {
+            LLVMBuildAdd(block.builder, args[0], args[1], raw_cstr("__ijssel_tmp"))
+        },
+        Builtin::WrappingSub => unsafe {
+            LLVMBuildSub(block.builder, args[0], args[1], raw_cstr("__ijssel_tmp"))
+        },
+        Builtin::WrappingMul => unsafe {
+            LLVMBuildMul(block.builder, args[0], args[1], raw_cstr("__ijssel_tmp"))
+        },
+    }
 }
@@ -0,0 +1,90 @@
+use super::intrinsics::intrinsic;
+use super::types::llvm_type;
+use crate::parser::{CastExpression, Type};
+use crate::raw_cstr;
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+
+/// Integers are truncated or extended according to the signedness of the
+/// value being cast. Floats are rounded towards zero and saturate at the
+/// bounds of the integer type like in Rust, NaN becomes zero. Casting `f64`
+/// to `f32` rounds to the nearest `f32`.
+pub fn compile_cast(block: &super::CodegenBlock, expression: &CastExpression) -> LLVMValueRef {
+    let value = super::compile_expression_to_instruction(block, &expression.value);
+    if block.is_terminated() {
+        return value;
+    }
+
+    let from = expression.value.ty();
+    let to = expression.target;
+    let destination_type = llvm_type(to);
+    let name = raw_cstr("__ijssel_tmp");
+
+    if from == to {
+        return value;
+    }
+
+    unsafe {
+        if from.is_float() && to.is_float() {
+            return if to == Type::F32 {
+                LLVMBuildFPTrunc(block.builder, value, destination_type, name)
+            } else {
+                LLVMBuildFPExt(block.builder, value, destination_type, name)
+            };
+        }
+
+        if from.is_float() {
+            return build_float_to_integer(block, value, from, to);
+        }
+
+        if to.is_float() {
+            return if from.is_signed_integer() {
+                LLVMBuildSIToFP(block.builder, value, destination_type, name)
+            } else {
+                LLVMBuildUIToFP(block.builder, value, destination_type, name)
+            };
+        }
+
+        // `bool` is an unsigned integer of a single bit
+        let from_width = from.integer_width().unwrap_or(1);
+        let to_width = to.integer_width().unwrap();
+
+        if from_width > to_width {
+            LLVMBuildTrunc(block.builder, value, destination_type, name)
+        } else if from_width == to_width {
+            value
+        } else if from.is_signed_integer() {
+            LLVMBuildSExt(block.builder, value, destination_type, name)
+        } else {
+            LLVMBuildZExt(block.builder, value, destination_type, name)
+        }
+    }
+}
+
+/// Calls `llvm.fptosi.sat` or `llvm.fptoui.sat`.
+pub fn build_float_to_integer(
+    block: &super::CodegenBlock,
+    value: LLVMValueRef,
+    from: Type,
+    to: Type,
+) -> LLVMValueRef {
+    let name = format!(
+        "llvm.fpto{}i.sat.i{}.{}",
+        if to.is_signed_integer() { "s" } else { "u" },
+        to.integer_width().unwrap(),
+        from
+    );
+
+    unsafe {
+        let function = intrinsic(block.module, &name, llvm_type(to), &mut [llvm_type(from)]);
+
+        let mut args = [value];
+        LLVMBuildCall(
+            block.builder,
+            function,
+            args.as_mut_ptr(),
+            args.len() as u32,
+            raw_cstr("__ijssel_tmp"),
+        )
+    }
+}
@@ -0,0 +1,152 @@
+use super::block::compile_expressions;
+use super::types::llvm_type;
+use crate::parser::tokenizer::BinaryOperator;
+use crate::parser::{BinaryExpression, BlockExpression, IfExpression, Type};
+use crate::raw_cstr;
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+
+use std::collections::HashMap;
+
+/// Compiles an `if` to a conditional branch to a block per branch, which both
+/// continue in a merge block where a phi node selects the resulting value.
+/// Branches that leave through `break` or `continue` do not reach the merge.
+pub fn compile_if(block: &super::CodegenBlock, expression: &IfExpression) -> LLVMValueRef {
+    let condition = super::compile_expression_to_instruction(block, &expression.condition);
+    if block.is_terminated() {
+        return super::never_value();
+    }
+
+    let then_block = block.append_basic_block("__ijssel_then");
+    let else_block = block.append_basic_block("__ijssel_else");
+
+    unsafe {
+        LLVMBuildCondBr(block.builder, condition, then_block, else_block);
+    }
+
+    let mut incoming: Vec<(LLVMValueRef, LLVMBasicBlockRef)> = Vec::with_capacity(2);
+
+    block.position_at_end(then_block);
+    let then_value = compile_branch(block, Some(&expression.then_block), expression.ty);
+    if !block.is_terminated() {
+        incoming.push((then_value, block.current_basic_block()));
+    }
+
+    block.position_at_end(else_block);
+    let else_value = compile_branch(block, expression.else_block.as_ref(), expression.ty);
+    if !block.is_terminated() {
+        incoming.push((else_value, block.current_basic_block()));
+    }
+
+    // branches can contain blocks of their own, so the merge block is only
+    // added once they are done to keep the blocks in source order
+    let merge_block = block.append_basic_block("__ijssel_merge");
+    for (_, branch_end) in &incoming {
+        block.position_at_end(*branch_end);
+        unsafe {
+            LLVMBuildBr(block.builder, merge_block);
+        }
+    }
+
+    block.position_at_end(merge_block);
+
+    if incoming.is_empty() {
+        unsafe {
+            LLVMBuildUnreachable(block.builder);
+        }
+        return super::never_value();
+    }
+
+    if expression.ty == Type::Void {
+        return super::never_value();
+    }
+
+    unsafe {
+        let phi = LLVMBuildPhi(
+            block.builder,
+            llvm_type(expression.ty),
+            raw_cstr("__ijssel_tmp"),
+        );
+        let (mut values, mut blocks): (Vec<_>, Vec<_>) = incoming.into_iter().unzip();
+        LLVMAddIncoming(
+            phi,
+            values.as_mut_ptr(),
+            blocks.as_mut_ptr(),
+            values.len() as u32,
+        );
+        phi
+    }
+}
+
+/// A missing or empty branch evaluates to zero, when the `if` has a value.
+fn compile_branch(
+    block: &super::CodegenBlock,
+    branch: Option<&BlockExpression>,
+    ty: Type,
+) -> LLVMValueRef {
+    let value = branch.and_then(|branch| compile_expressions(block, branch));
+
+    match value {
+        Some(value) => value,
+        None if ty == Type::Void => super::never_value(),
+        None => unsafe { LLVMConstNull(llvm_type(ty)) },
+    }
+}
+
+/// Compiles `&&` and `||` to a branch that skips the right side when the left
+/// side already decides the result, which is then the left side itself.
+pub fn compile_logical(block: &super::CodegenBlock, expression: &BinaryExpression) -> LLVMValueRef {
+    let left = super::compile_expression_to_instruction(block, &expression.left);
+    if block.is_terminated() {
+        return super::never_value();
+    }
+
+    let left_end = block.current_basic_block();
+
+    let right_block = block.append_basic_block("__ijssel_rhs");
+    let merge_block = block.append_basic_block("__ijssel_merge");
+
+    let short_circuit = unsafe {
+        match expression.operator {
+            BinaryOperator::And => {
+                LLVMBuildCondBr(block.builder, left, right_block, merge_block);
+                LLVMConstInt(LLVMInt1Type(), 0, 0)
+            }
+            _ => {
+                LLVMBuildCondBr(block.builder, left, merge_block, right_block);
+                LLVMConstInt(LLVMInt1Type(), 1, 0)
+            }
+        }
+    };
+
+    let mut incoming = vec![(short_circuit, left_end)];
+
+    block.position_at_end(right_block);
+    block.scopes.borrow_mut().push(HashMap::new());
+    let right = super::compile_expression_to_instruction(block, &expression.right);
+    block.scopes.borrow_mut().pop();
+    if !block.is_terminated() {
+        incoming.push((right, block.current_basic_block()));
+        unsafe {
+            LLVMBuildBr(block.builder, merge_block);
+        }
+    }
+
+    // the right side can contain blocks of its own, keep them in source order
+    unsafe {
+        LLVMMoveBasicBlockAfter(merge_block, LLVMGetLastBasicBlock(block.function));
+    }
+    block.position_at_end(merge_block);
+
+    unsafe {
+        let phi = LLVMBuildPhi(block.builder, LLVMInt1Type(), raw_cstr("__ijssel_tmp"));
+        let (mut values, mut blocks): (Vec<_>, Vec<_>) = incoming.into_iter().unzip();
+        LLVMAddIncoming(
+            phi,
+            values.as_mut_ptr(),
+            blocks.as_mut_ptr(),
+            values.len() as u32,
+        );
+        phi
+    }
+}
@@ -1,7 +1,44 @@
-use crate::parser::NumberLiteralExpression;
+use super::types::llvm_type;
+use crate::parser::expression::NumberValue;
+use crate::parser::{BooleanLiteralExpression, NumberLiteralExpression, StringLiteralExpression};
+use crate::raw_cstr;
 use llvm_sys::core::*;
 use llvm_sys::prelude::*;
 
-pub fn compile_int64_constant(number: &NumberLiteralExpression) -> LLVMValueRef {
-    unsafe { LLVMConstInt(LLVMInt64Type(), number.number as u64, 0) }
+pub fn compile_number_constant(number: &NumberLiteralExpression) -> LLVMValueRef {
+    match number.number {
+        NumberValue::Integer(integer) => unsafe {
+            LLVMConstInt(llvm_type(number.ty), integer as u64, 1)
+        },
+        NumberValue::Float(float) => unsafe { LLVMConstReal(llvm_type(number.ty), float) },
+    }
+}
+
+pub fn compile_boolean_constant(boolean: &BooleanLiteralExpression) -> LLVMValueRef {
+    unsafe { LLVMConstInt(LLVMInt1Type(), boolean.value as u64, 0) }
+}
+
+pub fn compile_string_constant(
+    module: LLVMModuleRef,
+    string: &StringLiteralExpression,
+) -> LLVMValueRef {
+    build_string_constant(module, &string.value)
+}
+
+/// Adds a NUL terminated string to the module as a private constant and
+/// returns a pointer to its first character. Unlike `LLVMBuildGlobalString`
+/// this allows the string to contain NUL characters.
+pub fn build_string_constant(module: LLVMModuleRef, value: &str) -> LLVMValueRef {
+    unsafe {
+        let initializer = LLVMConstString(value.as_ptr() as *const _, value.len() as u32, 0);
+        let global = LLVMAddGlobal(module, LLVMTypeOf(initializer), raw_cstr("__ijssel_str"));
+        LLVMSetInitializer(global, initializer);
+        LLVMSetGlobalConstant(global, 1);
+        LLVMSetLinkage(global, llvm_sys::LLVMLinkage::LLVMPrivateLinkage);
+        LLVMSetUnnamedAddress(global, llvm_sys::LLVMUnnamedAddr::LLVMGlobalUnnamedAddr);
+
+        let zero = LLVMConstInt(LLVMInt64Type(), 0, 0);
+        let mut indices = [zero, zero];
+        LLVMConstInBoundsGEP(global, indices.as_mut_ptr(), indices.len() as u32)
+    }
 }
@@ -1,66 +1,100 @@
-use crate::parser::{ExternFunctionExpression, FunctionExpression};
+use super::block::Binding;
+use super::types::llvm_type;
+use crate::parser::expression::FunctionArgument;
+use crate::parser::{ExternFunctionExpression, FunctionExpression, ReturnExpression, Type};
 use crate::raw_cstr;
 use llvm_sys::core::*;
 use llvm_sys::prelude::*;
 use std::collections::HashMap;
 
-pub fn compile_function(module: &LLVMModuleRef, expression: &FunctionExpression) {
-    let args_count = expression.arguments.len();
-    let mut args: Vec<LLVMTypeRef> = Vec::with_capacity(args_count);
+fn declare_prototype(
+    module: &LLVMModuleRef,
+    name: &str,
+    arguments: &[FunctionArgument],
+    return_type: Type,
+) -> LLVMValueRef {
+    let mut args: Vec<LLVMTypeRef> = Vec::with_capacity(arguments.len());
     let arg_type: *mut *mut llvm_sys::LLVMType = {
-        for _arg in &expression.arguments {
-            // TODO: types
-            args.push(unsafe { LLVMInt32Type() });
+        for arg in arguments {
+            args.push(llvm_type(arg.ty));
         }
 
         args.as_mut_ptr()
     };
 
-    let function_type = unsafe {
-        let fn_type = LLVMFunctionType(LLVMInt32Type(), arg_type, args.len() as u32, 0);
-        fn_type
-    };
+    let function_type =
+        unsafe { LLVMFunctionType(llvm_type(return_type), arg_type, args.len() as u32, 0) };
 
-    let function_name = expression.name.as_str();
-    let function = unsafe { LLVMAddFunction(*module, raw_cstr(function_name), function_type) };
+    unsafe { LLVMAddFunction(*module, raw_cstr(name), function_type) }
+}
 
-    let mut function_bindings: super::block::BindingsHashMap = HashMap::new();
+/// Adds the prototype of a function to the module, without its body, so that
+/// calls to it can be compiled regardless of where it is defined.
+pub fn declare_function(module: &LLVMModuleRef, expression: &FunctionExpression) {
+    let function = declare_prototype(
+        module,
+        expression.name.as_str(),
+        &expression.arguments,
+        expression.return_type,
+    );
 
-    for (i, item) in expression.arguments.clone().into_iter().enumerate() {
+    for (i, item) in expression.arguments.iter().enumerate() {
         let binding = raw_cstr(item.binding_name.as_str());
-        let value_ref = unsafe {
+        unsafe {
             let value = LLVMGetParam(function, i as u32);
             LLVMSetValueName2(value, binding, item.binding_name.len());
-            value
-        };
-
-        function_bindings.insert(item.binding_name, value_ref);
+        }
     }
+}
 
-    let block = super::block::compile_block(module, &expression.body, function_bindings);
+/// Compiles the body of a function previously declared by `declare_function`.
+pub fn compile_function(
+    module: &LLVMModuleRef,
+    expression: &FunctionExpression,
+    checked_arithmetic: bool,
+) {
+    let function_name = expression.name.as_str();
+    let function = unsafe { LLVMGetNamedFunction(*module, raw_cstr(function_name)) };
 
-    unsafe { LLVMAppendExistingBasicBlock(function, block) }
-}
+    let mut function_bindings: super::block::BindingsHashMap = HashMap::new();
 
-pub fn compile_extern_function(module: &LLVMModuleRef, expression: &ExternFunctionExpression) {
-    let args_count = expression.arguments.len();
-    let mut args: Vec<LLVMTypeRef> = Vec::with_capacity(args_count);
-    let arg_type: *mut *mut llvm_sys::LLVMType = {
-        for _arg in &expression.arguments {
-            // TODO: types
-            args.push(unsafe { LLVMInt32Type() });
-        }
+    for (i, item) in expression.arguments.clone().into_iter().enumerate() {
+        let value_ref = unsafe { LLVMGetParam(function, i as u32) };
+        function_bindings.insert(item.binding_name, Binding::Value(value_ref));
+    }
 
-        args.as_mut_ptr()
-    };
+    super::block::compile_block(
+        module,
+        function,
+        &expression.body,
+        expression.return_type,
+        function_bindings,
+        checked_arithmetic,
+    );
+}
 
-    let function_type = unsafe {
-        let fn_type = LLVMFunctionType(LLVMInt32Type(), arg_type, args.len() as u32, 0);
-        fn_type
+/// Compiles `return`, which terminates the current basic block. Without a
+/// value the function returns `void`, sema has checked that it does.
+pub fn compile_return(block: &super::CodegenBlock, expression: &ReturnExpression) -> LLVMValueRef {
+    let value = match &expression.value {
+        Some(value) => super::compile_expression_to_instruction(block, value),
+        None => super::never_value(),
     };
 
-    let function_name = expression.name.as_str();
-    let function = unsafe { LLVMAddFunction(*module, raw_cstr(function_name), function_type) };
+    if !block.is_terminated() {
+        block.build_return(value);
+    }
+
+    super::never_value()
+}
+
+pub fn compile_extern_function(module: &LLVMModuleRef, expression: &ExternFunctionExpression) {
+    let function = declare_prototype(
+        module,
+        expression.name.as_str(),
+        &expression.arguments,
+        expression.return_type,
+    );
 
     unsafe { LLVMSetLinkage(function, llvm_sys::LLVMLinkage::LLVMExternalLinkage) }
 }
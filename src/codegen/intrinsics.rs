@@ -0,0 +1,70 @@
+use crate::parser::Span;
+use crate::raw_cstr;
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+
+/// Looks up an LLVM intrinsic, declaring it the first time it is needed.
+pub fn intrinsic(
+    module: LLVMModuleRef,
+    name: &str,
+    return_type: LLVMTypeRef,
+    parameters: &mut [LLVMTypeRef],
+) -> LLVMValueRef {
+    let existing = unsafe { LLVMGetNamedFunction(module, raw_cstr(name)) };
+    if !existing.is_null() {
+        return existing;
+    }
+
+    unsafe {
+        let function_type = LLVMFunctionType(
+            return_type,
+            parameters.as_mut_ptr(),
+            parameters.len() as u32,
+            0,
+        );
+
+        LLVMAddFunction(module, raw_cstr(name), function_type)
+    }
+}
+
+/// Calls `llvm.{s,u}{add,sub,mul}.with.overflow` and panics with `message`
+/// when the result does not fit, returning the result otherwise. `operation`
+/// is one of `add`, `sub` or `mul`.
+pub fn build_checked_arithmetic(
+    block: &super::CodegenBlock,
+    operation: &str,
+    signed: bool,
+    left: LLVMValueRef,
+    right: LLVMValueRef,
+    message: &str,
+    span: Span,
+) -> LLVMValueRef {
+    unsafe {
+        let ty = LLVMTypeOf(left);
+        let name = format!(
+            "llvm.{}{}.with.overflow.i{}",
+            if signed { "s" } else { "u" },
+            operation,
+            LLVMGetIntTypeWidth(ty)
+        );
+
+        let mut fields = [ty, LLVMInt1Type()];
+        let result_type = LLVMStructType(fields.as_mut_ptr(), fields.len() as u32, 0);
+        let function = intrinsic(block.module, &name, result_type, &mut [ty, ty]);
+
+        let mut args = [left, right];
+        let result = LLVMBuildCall(
+            block.builder,
+            function,
+            args.as_mut_ptr(),
+            args.len() as u32,
+            raw_cstr("__ijssel_tmp"),
+        );
+
+        let value = LLVMBuildExtractValue(block.builder, result, 0, raw_cstr("__ijssel_tmp"));
+        let overflows = LLVMBuildExtractValue(block.builder, result, 1, raw_cstr("__ijssel_tmp"));
+        super::runtime::build_panic_if(block, overflows, message, span);
+
+        value
+    }
+}
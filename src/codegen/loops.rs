@@ -0,0 +1,72 @@
+use super::block::{compile_expressions, LoopTarget};
+use crate::parser::{LoopControlExpression, WhileExpression};
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+
+/// Compiles a `while` loop to a header block that checks the condition, the
+/// body, which jumps back to the header, and an exit block after the loop.
+pub fn compile_while(block: &super::CodegenBlock, expression: &WhileExpression) -> LLVMValueRef {
+    let header_block = block.append_basic_block("__ijssel_loop_header");
+    unsafe {
+        LLVMBuildBr(block.builder, header_block);
+    }
+
+    block.position_at_end(header_block);
+    let condition = super::compile_expression_to_instruction(block, &expression.condition);
+    if block.is_terminated() {
+        return super::never_value();
+    }
+
+    let body_block = block.append_basic_block("__ijssel_loop_body");
+    let exit_block = block.append_basic_block("__ijssel_loop_exit");
+    unsafe {
+        LLVMBuildCondBr(block.builder, condition, body_block, exit_block);
+    }
+
+    block.loops.borrow_mut().push(LoopTarget {
+        break_block: exit_block,
+        continue_block: header_block,
+    });
+
+    block.position_at_end(body_block);
+    compile_expressions(block, &expression.body);
+    if !block.is_terminated() {
+        unsafe {
+            LLVMBuildBr(block.builder, header_block);
+        }
+    }
+
+    block.loops.borrow_mut().pop();
+
+    // the body can contain blocks of its own, keep the blocks in source order
+    unsafe {
+        LLVMMoveBasicBlockAfter(exit_block, LLVMGetLastBasicBlock(block.function));
+    }
+    block.position_at_end(exit_block);
+
+    // a loop always evaluates to zero
+    unsafe { LLVMConstInt(LLVMInt64Type(), 0, 0) }
+}
+
+pub fn compile_loop_control(
+    block: &super::CodegenBlock,
+    expression: &LoopControlExpression,
+) -> LLVMValueRef {
+    let target = *block
+        .loops
+        .borrow()
+        .last()
+        .expect("Loop control outside of a loop, sema should have caught this");
+
+    let destination = if expression.is_break {
+        target.break_block
+    } else {
+        target.continue_block
+    };
+
+    unsafe {
+        LLVMBuildBr(block.builder, destination);
+    }
+
+    super::never_value()
+}
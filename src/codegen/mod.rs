@@ -1,19 +1,41 @@
 pub mod binary;
 pub mod block;
 pub mod call;
+pub mod cast;
+pub mod conditional;
 pub mod constants;
 pub mod function;
+pub mod intrinsics;
+pub mod loops;
 pub mod module;
+pub mod runtime;
+pub mod types;
+pub mod unary;
 pub mod variable;
+pub mod verify;
+
+#[cfg(test)]
+mod tests;
 
 pub use block::CodegenBlock;
 
+use crate::diagnostics::Diagnostic;
 use crate::parser::Expression;
 use crate::parser::Module;
 use llvm_sys::prelude::*;
 
-pub fn compile_application(module: Module) -> LLVMModuleRef {
-    self::module::compile_module(&module)
+/// Compiles a module to LLVM IR, returning internal compiler errors when the
+/// generated IR does not pass LLVM's verifier. Debug builds panic when integer
+/// arithmetic overflows, release builds wrap around.
+pub fn compile_application(module: Module, debug: bool) -> Result<LLVMModuleRef, Vec<Diagnostic>> {
+    let llvm_module = self::module::compile_module(&module, debug);
+
+    let diagnostics = verify::verify_module(llvm_module, &module);
+    if diagnostics.is_empty() {
+        Ok(llvm_module)
+    } else {
+        Err(diagnostics)
+    }
 }
 
 pub fn compile_expression_to_instruction(
@@ -23,10 +45,34 @@ pub fn compile_expression_to_instruction(
     use crate::parser::Expression::*;
 
     match expression {
+        Binary(expr) if expr.operator.is_logical() => {
+            conditional::compile_logical(containing_block, expr)
+        }
         Binary(expr) => binary::compile_binary_expression_to_instruction(containing_block, expr),
-        NumberLiteral(literal) => constants::compile_int64_constant(literal),
+        Unary(expr) => unary::compile_unary_expression(containing_block, expr),
+        Cast(expr) => cast::compile_cast(containing_block, expr),
+        NumberLiteral(literal) => constants::compile_number_constant(literal),
+        BooleanLiteral(literal) => constants::compile_boolean_constant(literal),
+        StringLiteral(literal) => {
+            constants::compile_string_constant(containing_block.module, literal)
+        }
         Variable(expr) => variable::compile_variable_expression(containing_block, expr),
+        Let(expr) => variable::compile_let(containing_block, expr),
+        Assign(expr) => variable::compile_assign(containing_block, expr),
         Call(expr) => call::compile_call(containing_block, expr),
-        _ => unimplemented!(),
+        If(expr) => conditional::compile_if(containing_block, expr),
+        While(expr) => loops::compile_while(containing_block, expr),
+        LoopControl(expr) => loops::compile_loop_control(containing_block, expr),
+        Return(expr) => function::compile_return(containing_block, expr),
+        Function(_) | ExternFunction(_) => {
+            unreachable!("sema rejects nested declarations: {:?}", expression)
+        }
     }
 }
+
+/// The value of an expression of type `!` or `void`. Compiling the former
+/// terminates the current basic block and sema does not allow using the
+/// latter, so the value is never used.
+pub fn never_value() -> LLVMValueRef {
+    unsafe { llvm_sys::core::LLVMGetUndef(llvm_sys::core::LLVMInt64Type()) }
+}
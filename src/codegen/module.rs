@@ -3,20 +3,38 @@ use crate::parser::{Expression, Module};
 use llvm_sys::core::*;
 use llvm_sys::prelude::*;
 
-pub fn compile_module(module: &Module) -> LLVMModuleRef {
+pub fn compile_module(module: &Module, checked_arithmetic: bool) -> LLVMModuleRef {
     let module_name = crate::raw_cstr(module.name.as_str());
     let llvm_module = unsafe { LLVMModuleCreateWithName(module_name) };
+    unsafe {
+        LLVMSetSourceFileName(
+            llvm_module,
+            module.file_name.as_ptr() as *const _,
+            module.file_name.len(),
+        );
+    }
 
+    // Declare every prototype before compiling any body, so that functions can
+    // call functions that are defined further down, or each other.
     for expression in &module.expressions {
         match expression {
-            Expression::Function(expr) => super::function::compile_function(&llvm_module, &**expr),
+            Expression::Function(expr) => super::function::declare_function(&llvm_module, expr),
             Expression::ExternFunction(expr) => {
-                super::function::compile_extern_function(&llvm_module, &**expr)
+                super::function::compile_extern_function(&llvm_module, expr)
             }
 
-            expr => panic!("Incorrect module level expression {:?}", expr),
+            expr => panic!(
+                "Incorrect module level expression {:?}, sema should have caught this",
+                expr
+            ),
+        }
+    }
+
+    for expression in &module.expressions {
+        if let Expression::Function(expr) = expression {
+            super::function::compile_function(&llvm_module, expr, checked_arithmetic)
         }
     }
 
-    return llvm_module;
+    llvm_module
 }
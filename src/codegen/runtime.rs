@@ -0,0 +1,86 @@
+use crate::parser::Span;
+use crate::raw_cstr;
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+
+/// Name of the routine in the C std that reports a runtime error and exits.
+/// Sema reserves it, so a function found under this name is always this one.
+pub const PANIC_FUNCTION: &str = "ijssel_panic";
+
+/// Declares `void ijssel_panic(const char *message, const char *file, long
+/// line, long column)` the first time it is needed.
+fn panic_function(module: LLVMModuleRef) -> LLVMValueRef {
+    let existing = unsafe { LLVMGetNamedFunction(module, raw_cstr(PANIC_FUNCTION)) };
+    if !existing.is_null() {
+        return existing;
+    }
+
+    unsafe {
+        let mut parameters = [
+            LLVMPointerType(LLVMInt8Type(), 0),
+            LLVMPointerType(LLVMInt8Type(), 0),
+            LLVMInt64Type(),
+            LLVMInt64Type(),
+        ];
+        let function_type = LLVMFunctionType(
+            LLVMVoidType(),
+            parameters.as_mut_ptr(),
+            parameters.len() as u32,
+            0,
+        );
+
+        LLVMAddFunction(module, raw_cstr(PANIC_FUNCTION), function_type)
+    }
+}
+
+/// Branches to a block that panics with `message` when `condition` is true,
+/// and leaves the builder positioned where execution continues otherwise.
+pub fn build_panic_if(
+    block: &super::CodegenBlock,
+    condition: LLVMValueRef,
+    message: &str,
+    span: Span,
+) {
+    let panic_block = block.append_basic_block("__ijssel_panic");
+    let continue_block = block.append_basic_block("__ijssel_continue");
+
+    unsafe {
+        LLVMBuildCondBr(block.builder, condition, panic_block, continue_block);
+    }
+
+    block.position_at_end(panic_block);
+    build_panic(block, message, span);
+
+    block.position_at_end(continue_block);
+}
+
+/// Calls the panic routine with the location of `span` in the source file of
+/// the module, this never returns.
+fn build_panic(block: &super::CodegenBlock, message: &str, span: Span) {
+    let function = panic_function(block.module);
+
+    unsafe {
+        let mut length = 0;
+        let file_name = LLVMGetSourceFileName(block.module, &mut length);
+        let file_name = std::slice::from_raw_parts(file_name as *const u8, length);
+
+        let mut args = [
+            super::constants::build_string_constant(block.module, message),
+            super::constants::build_string_constant(
+                block.module,
+                &String::from_utf8_lossy(file_name),
+            ),
+            LLVMConstInt(LLVMInt64Type(), span.line as u64, 0),
+            LLVMConstInt(LLVMInt64Type(), span.column as u64, 0),
+        ];
+
+        LLVMBuildCall(
+            block.builder,
+            function,
+            args.as_mut_ptr(),
+            args.len() as u32,
+            raw_cstr(""),
+        );
+        LLVMBuildUnreachable(block.builder);
+    }
+}
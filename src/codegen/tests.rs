@@ -0,0 +1,381 @@
+use super::runtime::PANIC_FUNCTION;
+use crate::parser::parser::Parser;
+use crate::parser::tokenizer::Tokenizer;
+use crate::raw_cstr;
+use llvm_sys::core::*;
+use llvm_sys::execution_engine::*;
+use llvm_sys::prelude::*;
+use llvm_sys::support::LLVMAddSymbol;
+use llvm_sys::target::*;
+
+use std::cell::RefCell;
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+use std::sync::mpsc::{channel, Sender};
+
+/// Compiles a program, which must have no errors. Integer arithmetic is
+/// checked for overflow when `debug` is set.
+fn compile_with(source: &str, debug: bool) -> LLVMModuleRef {
+    let mut tokenizer = Tokenizer::new(source);
+    tokenizer.parse();
+    assert!(
+        tokenizer.diagnostics.is_empty(),
+        "{:?}",
+        tokenizer.diagnostics
+    );
+
+    let mut parser = Parser::new(&tokenizer.tokens);
+    parser.parse_module();
+    parser.module.file_name = "test.ij".to_string();
+    assert!(parser.diagnostics.is_empty(), "{:?}", parser.diagnostics);
+
+    let diagnostics = crate::sema::analyse(&mut parser.module);
+    assert!(
+        !diagnostics.iter().any(|d| d.is_error()),
+        "{:?}",
+        diagnostics
+    );
+
+    super::compile_application(parser.module, debug)
+        .unwrap_or_else(|diagnostics| panic!("{:?}", diagnostics))
+}
+
+/// Compiles a program with overflow checks, which must have no errors.
+fn compile(source: &str) -> LLVMModuleRef {
+    compile_with(source, true)
+}
+
+/// The IR of a program, as `--type ll` prints it.
+fn compile_to_ir(source: &str) -> String {
+    unsafe {
+        let module = compile(source);
+        let ir = LLVMPrintModuleToString(module);
+        let string = CStr::from_ptr(ir).to_string_lossy().into_owned();
+
+        LLVMDisposeMessage(ir);
+        LLVMDisposeModule(module);
+        string
+    }
+}
+
+/// How a program run by `execute` ended.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Returned(i64),
+    /// The runtime panicked, with the location and message it prints.
+    Panicked(String),
+}
+
+thread_local! {
+    /// Where the panic hook of the program running on this thread reports.
+    static OUTCOME: RefCell<Option<Sender<Outcome>>> = const { RefCell::new(None) };
+}
+
+/// Stands in for `ijssel_panic` from the C std, which exits the process.
+/// The program cannot continue after it either, so its thread is parked for
+/// good once the panic is reported.
+extern "C" fn panic_hook(message: *const c_char, file: *const c_char, line: i64, column: i64) -> ! {
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+    let file = unsafe { CStr::from_ptr(file) }.to_string_lossy();
+    let outcome = Outcome::Panicked(format!("{}:{}:{}: {}", file, line, column, message));
+    OUTCOME.with(|sender| sender.borrow().as_ref().unwrap().send(outcome).unwrap());
+
+    loop {
+        std::thread::park();
+    }
+}
+
+/// Runs the `main` of a compiled program, which must return an `i64` and
+/// cannot call any extern but the panic routine.
+fn execute(module: LLVMModuleRef) -> Outcome {
+    unsafe {
+        LLVMLinkInMCJIT();
+        LLVM_InitializeNativeTarget();
+        LLVM_InitializeNativeAsmPrinter();
+        LLVMAddSymbol(raw_cstr(PANIC_FUNCTION), panic_hook as *mut c_void);
+
+        let mut engine = std::mem::MaybeUninit::uninit();
+        let mut error = std::ptr::null_mut();
+        assert_eq!(
+            LLVMCreateExecutionEngineForModule(engine.as_mut_ptr(), module, &mut error),
+            0,
+            "could not create an execution engine"
+        );
+        let engine = engine.assume_init();
+
+        let address = LLVMGetFunctionAddress(engine, raw_cstr("main"));
+        assert_ne!(address, 0, "the program has no `main`");
+        let main: extern "C" fn() -> i64 = std::mem::transmute(address);
+
+        let (sender, receiver) = channel();
+        std::thread::spawn(move || {
+            OUTCOME.with(|outcome| *outcome.borrow_mut() = Some(sender.clone()));
+            sender.send(Outcome::Returned(main())).unwrap();
+        });
+        let outcome = receiver.recv().unwrap();
+
+        // a thread that panicked is still inside the code of the engine
+        if let Outcome::Returned(_) = outcome {
+            LLVMDisposeExecutionEngine(engine);
+        }
+        outcome
+    }
+}
+
+/// Compiles a program with overflow checks and runs it, it must return.
+fn run(source: &str) -> i64 {
+    match execute(compile(source)) {
+        Outcome::Returned(value) => value,
+        outcome => panic!("the program did not return: {:?}", outcome),
+    }
+}
+
+/// Compiles a program with overflow checks and runs it, it must panic.
+fn run_to_panic(source: &str) -> String {
+    match execute(compile(source)) {
+        Outcome::Panicked(message) => message,
+        outcome => panic!("the program did not panic: {:?}", outcome),
+    }
+}
+
+#[test]
+fn binding_on_the_left_is_visible_on_the_right() {
+    assert_eq!(run("fn main -> i64 do (let q = 3) + q end"), 6);
+}
+
+#[test]
+fn assignment_on_the_left_happens_before_the_right() {
+    let source = "
+        fn main -> i64 do
+            var y = 1
+            (y = 10) + y
+        end
+    ";
+    assert_eq!(run(source), 20);
+}
+
+#[test]
+fn binary_operands_are_evaluated_left_to_right() {
+    let source = "
+        fn p(x: i64) -> i64 do x end
+
+        fn main -> i64 do
+            p(1) + p(2)
+        end
+    ";
+    let ir = compile_to_ir(source);
+
+    let first = ir.find("@p(i64 1)").expect("`p(1)` is called");
+    let second = ir.find("@p(i64 2)").expect("`p(2)` is called");
+    assert!(first < second, "`p(2)` is called first:\n{}", ir);
+}
+
+#[test]
+fn generic_function_can_be_called_with_different_types() {
+    let source = "
+        fn id(x) do x end
+
+        fn main -> i64 do
+            if id(true) do id(41) + 1 else 0 end
+        end
+    ";
+    assert_eq!(run(source), 42);
+}
+
+#[test]
+fn generic_function_is_copied_for_each_instance() {
+    let source = "
+        fn id(x) do x end
+        fn fib(n) do if n < 2 do n else fib(n - 1) + fib(n - 2) end end
+
+        fn main -> i64 do
+            id(2.5) as i64 + id(1) + fib(10 as u8) as i64
+        end
+    ";
+    let ir = compile_to_ir(source);
+
+    assert!(ir.contains("define i64 @id(i64"), "{}", ir);
+    assert!(ir.contains("define double @id.f64(double"), "{}", ir);
+    assert!(ir.contains("define i8 @fib.u8(i8"), "{}", ir);
+    assert!(ir.contains("call i8 @fib.u8(i8"), "{}", ir);
+}
+
+#[test]
+fn binding_on_the_right_of_a_logical_operator_is_scoped_to_it() {
+    let source = "
+        fn main -> i64 do
+            let x = 1
+            if false || (let x = true) && x do x else 0 end
+        end
+    ";
+    assert_eq!(run(source), 1);
+}
+
+#[test]
+fn division_by_zero_panics() {
+    let source = "
+        fn div(a: i64, b: i64) do a / b end
+        fn rem(a: i64, b: i64) do a % b end
+
+        fn main -> i64 do
+            div(7, 2) + rem(7, 2) + div(1, 0)
+        end
+    ";
+    assert_eq!(
+        run_to_panic(source),
+        "test.ij:2:35: attempt to divide by zero"
+    );
+
+    let source = "
+        fn rem(a: i64, b: i64) do a % b end
+
+        fn main -> i64 do
+            rem(7, 0)
+        end
+    ";
+    assert_eq!(
+        run_to_panic(source),
+        "test.ij:2:35: attempt to calculate the remainder with a divisor of zero"
+    );
+}
+
+#[test]
+fn dividing_the_minimum_by_minus_one_panics() {
+    let source = "
+        fn div(a: i64, b: i64) do a / b end
+
+        fn main -> i64 do
+            div(-9223372036854775807 - 1, -1)
+        end
+    ";
+    assert_eq!(
+        run_to_panic(source),
+        "test.ij:2:35: attempt to divide with overflow"
+    );
+
+    let source = "
+        fn div(a: i64, b: i64) do a / b end
+
+        fn main -> i64 do
+            div(-9223372036854775807 - 1, 1) + 4
+        end
+    ";
+    assert_eq!(run(source), -9223372036854775804);
+}
+
+#[test]
+fn overflow_panics_in_debug_builds_and_wraps_in_release_builds() {
+    let source = "
+        fn add(a: i64, b: i64) do a + b end
+
+        fn main -> i64 do
+            add(9223372036854775807, 1)
+        end
+    ";
+
+    assert_eq!(
+        execute(compile_with(source, true)),
+        Outcome::Panicked("test.ij:2:35: attempt to add with overflow".to_string())
+    );
+    assert_eq!(
+        execute(compile_with(source, false)),
+        Outcome::Returned(i64::MIN)
+    );
+}
+
+#[test]
+fn wrapping_builtins_never_panic() {
+    let source = "
+        fn main -> i64 do
+            let max = 9223372036854775807
+            let min = -9223372036854775807 - 1
+            if wrapping_add(max, 1) == min
+                && wrapping_sub(min, 1) == max
+                && wrapping_mul(max, 2) == -2
+                && wrapping_add(200 as u8, 100 as u8) == 44 as u8
+            do 1 else 0 end
+        end
+    ";
+    assert_eq!(run(source), 1);
+}
+
+#[test]
+fn if_is_an_expression() {
+    let source = "
+        fn sign(x: i64) -> i64 do
+            if x < 0 do -1 else if x == 0 do 0 else 1 end
+        end
+
+        fn main -> i64 do
+            let magnitude = if sign(-5) < 0 do 100 else 200 end
+            magnitude + sign(-5) * 10 + sign(0) + sign(7)
+        end
+    ";
+    assert_eq!(run(source), 91);
+}
+
+#[test]
+fn if_branches_can_leave_a_loop() {
+    let source = "
+        fn main -> i64 do
+            var i = 0
+            while true do
+                i = if i < 5 do i + 1 else break end
+            end
+            i
+        end
+    ";
+    assert_eq!(run(source), 5);
+}
+
+#[test]
+fn while_with_break_and_continue() {
+    let source = "
+        fn main -> i64 do
+            var i = 0
+            var total = 0
+            while true do
+                i = i + 1
+                if i > 10 do break end
+                if i % 2 == 0 do continue end
+                total = total + i
+            end
+            total * 100 + i
+        end
+    ";
+    assert_eq!(run(source), 2511);
+}
+
+#[test]
+fn break_and_continue_target_the_innermost_loop() {
+    let source = "
+        fn main -> i64 do
+            var count = 0
+            var i = 0
+            while i < 3 do
+                i = i + 1
+                var j = 0
+                while true do
+                    j = j + 1
+                    if j == 2 do continue end
+                    if j > 4 do break end
+                    count = count + 1
+                end
+            end
+            count
+        end
+    ";
+    assert_eq!(run(source), 9);
+}
+
+#[test]
+fn while_evaluates_to_zero() {
+    let source = "
+        fn main -> i64 do
+            var i = 0
+            let value = while i < 3 do i = i + 1 end
+            value * 10 + i
+        end
+    ";
+    assert_eq!(run(source), 3);
+}
@@ -0,0 +1,26 @@
+use crate::parser::Type;
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+
+/// The LLVM type used to represent values of an ijssel type. `i64` matches
+/// `long`, `f64` matches `double` and `f32` matches `float` in the C std,
+/// `bool` is a single bit and `ptr` is a `char *`. The other integer types
+/// match the C types of the same width, LLVM does not distinguish signed
+/// from unsigned ones. `void` is only used for return types.
+pub fn llvm_type(ty: Type) -> LLVMTypeRef {
+    match ty {
+        Type::Void => unsafe { LLVMVoidType() },
+        Type::Bool => unsafe { LLVMInt1Type() },
+        Type::I8 | Type::U8 => unsafe { LLVMInt8Type() },
+        Type::I16 | Type::U16 => unsafe { LLVMInt16Type() },
+        Type::I32 | Type::U32 => unsafe { LLVMInt32Type() },
+        Type::I64 | Type::U64 | Type::Usize => unsafe { LLVMInt64Type() },
+        Type::F32 => unsafe { LLVMFloatType() },
+        Type::F64 => unsafe { LLVMDoubleType() },
+        Type::Ptr => unsafe { LLVMPointerType(LLVMInt8Type(), 0) },
+        Type::Never => panic!("Expressions of type `!` have no value"),
+        Type::Unknown | Type::Variable(_) => {
+            panic!("Type was not inferred, sema should have caught this")
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::parser::tokenizer::UnaryOperator;
+use crate::parser::UnaryExpression;
+use crate::raw_cstr;
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+
+pub fn compile_unary_expression(
+    containing_block: &super::CodegenBlock,
+    expression: &UnaryExpression,
+) -> LLVMValueRef {
+    let operand = super::compile_expression_to_instruction(containing_block, &expression.operand);
+    if containing_block.is_terminated() {
+        return operand;
+    }
+
+    match expression.operator {
+        UnaryOperator::Neg if expression.ty.is_float() => unsafe {
+            LLVMBuildFNeg(containing_block.builder, operand, raw_cstr("__ijssel_tmp"))
+        },
+        // negating the minimum value of a signed integer overflows
+        UnaryOperator::Neg if containing_block.checked_arithmetic => {
+            super::intrinsics::build_checked_arithmetic(
+                containing_block,
+                "sub",
+                true,
+                unsafe { LLVMConstNull(LLVMTypeOf(operand)) },
+                operand,
+                "attempt to negate with overflow",
+                expression.span,
+            )
+        }
+        UnaryOperator::Neg => unsafe {
+            LLVMBuildNeg(containing_block.builder, operand, raw_cstr("__ijssel_tmp"))
+        },
+        // a `bool` is a single bit, so flipping every bit negates it
+        UnaryOperator::Not | UnaryOperator::BitNot => unsafe {
+            LLVMBuildNot(containing_block.builder, operand, raw_cstr("__ijssel_tmp"))
+        },
+    }
+}
@@ -1,13 +1,69 @@
-use crate::parser::VariableExpression;
+use super::block::Binding;
+use super::types::llvm_type;
+use crate::parser::{AssignExpression, LetExpression, VariableExpression};
+use crate::raw_cstr;
+use llvm_sys::core::*;
 use llvm_sys::prelude::*;
 
 pub fn compile_variable_expression(
     block: &super::CodegenBlock,
     expression: &VariableExpression,
 ) -> LLVMValueRef {
-    if let Some(value) = block.bindings.get(&expression.binding) {
-        *value
+    match block.lookup(&expression.binding) {
+        Some(Binding::Value(value)) => value,
+        Some(Binding::Slot { pointer, ty }) => unsafe {
+            LLVMBuildLoad2(
+                block.builder,
+                ty,
+                pointer,
+                raw_cstr(expression.binding.as_str()),
+            )
+        },
+        None => panic!(
+            "Unknown variable {}, sema should have caught this",
+            expression.binding
+        ),
+    }
+}
+
+/// `let` binds the value directly, `var` stores it in a new stack slot.
+pub fn compile_let(block: &super::CodegenBlock, expression: &LetExpression) -> LLVMValueRef {
+    let value = super::compile_expression_to_instruction(block, &expression.value);
+    if block.is_terminated() {
+        return value;
+    }
+
+    let binding = if expression.mutable {
+        let ty = llvm_type(expression.ty);
+        let pointer = block.build_entry_alloca(ty, &expression.binding);
+        unsafe {
+            LLVMBuildStore(block.builder, value, pointer);
+        }
+
+        Binding::Slot { pointer, ty }
     } else {
-        panic!("Unknown variable {}", expression.binding);
+        Binding::Value(value)
+    };
+
+    block.declare(&expression.binding, binding);
+    value
+}
+
+pub fn compile_assign(block: &super::CodegenBlock, expression: &AssignExpression) -> LLVMValueRef {
+    let value = super::compile_expression_to_instruction(block, &expression.value);
+    if block.is_terminated() {
+        return value;
     }
+
+    match block.lookup(&expression.binding) {
+        Some(Binding::Slot { pointer, .. }) => unsafe {
+            LLVMBuildStore(block.builder, value, pointer);
+        },
+        _ => panic!(
+            "Assignment to immutable variable {}, sema should have caught this",
+            expression.binding
+        ),
+    }
+
+    value
 }
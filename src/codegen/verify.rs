@@ -0,0 +1,71 @@
+use crate::diagnostics::Diagnostic;
+use crate::parser::{Expression, Module};
+use crate::raw_cstr;
+use llvm_sys::analysis::*;
+use llvm_sys::core::*;
+use llvm_sys::prelude::*;
+
+use std::ffi::CStr;
+
+/// Runs LLVM's verifier over every function and then the whole module. Any
+/// failure is a bug in codegen, so it is reported as an internal compiler
+/// error pointing at the ijssel function that produced the invalid IR.
+pub fn verify_module(llvm_module: LLVMModuleRef, module: &Module) -> Vec<Diagnostic> {
+    let (is_broken, message) = unsafe {
+        let mut message: *mut libc::c_char = std::ptr::null_mut();
+        let is_broken = LLVMVerifyModule(
+            llvm_module,
+            LLVMVerifierFailureAction::LLVMReturnStatusAction,
+            &mut message,
+        ) != 0;
+
+        let string = if message.is_null() {
+            String::new()
+        } else {
+            let string = CStr::from_ptr(message).to_string_lossy().into_owned();
+            LLVMDisposeMessage(message);
+            string
+        };
+
+        (is_broken, string)
+    };
+
+    if !is_broken {
+        return Vec::new();
+    }
+
+    let verifier_note = format!("LLVM verifier output:\n{}", message.trim_end());
+    let mut diagnostics = Vec::new();
+
+    for expression in &module.expressions {
+        if let Expression::Function(expr) = expression {
+            let function = unsafe { LLVMGetNamedFunction(llvm_module, raw_cstr(&expr.name)) };
+            if function.is_null() {
+                continue;
+            }
+
+            let function_is_broken = unsafe {
+                LLVMVerifyFunction(function, LLVMVerifierFailureAction::LLVMReturnStatusAction) != 0
+            };
+
+            if function_is_broken {
+                diagnostics.push(
+                    Diagnostic::bug(format!(
+                        "invalid LLVM IR generated for function `{}`",
+                        expr.name
+                    ))
+                    .with_primary(expr.name_span, "while compiling this function")
+                    .with_note(verifier_note.clone()),
+                );
+            }
+        }
+    }
+
+    // the module can be broken without any single function being at fault,
+    // for example when declarations conflict
+    if diagnostics.is_empty() {
+        diagnostics.push(Diagnostic::bug("invalid LLVM module generated").with_note(verifier_note));
+    }
+
+    diagnostics
+}
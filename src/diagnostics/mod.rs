@@ -0,0 +1,107 @@
+pub mod render;
+
+pub use render::render;
+
+use crate::parser::Span;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    /// Code that compiles but is most likely a mistake.
+    Warning,
+    /// A bug in the compiler rather than in the program being compiled.
+    Bug,
+}
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Bug => "error: internal compiler error",
+        }
+    }
+}
+
+/// A message attached to a region of the source. Primary labels point at the
+/// cause of a diagnostic, secondary labels add context.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+impl Label {
+    pub fn new(span: Span, message: impl Into<String>, primary: bool) -> Self {
+        Label {
+            span,
+            message: message.into(),
+            primary,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    pub fn bug(message: impl Into<String>) -> Self {
+        Diagnostic::new(Severity::Bug, message)
+            .with_note("this is a bug in the ijssel compiler, please report it")
+    }
+
+    pub fn with_primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label::new(span, message, true));
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label::new(span, message, false));
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Where the diagnostic points: its primary label, or its first label
+    /// when it has no primary one.
+    pub fn primary_span(&self) -> Option<Span> {
+        self.labels
+            .iter()
+            .find(|label| label.primary)
+            .or_else(|| self.labels.first())
+            .map(|label| label.span)
+    }
+
+    pub fn is_error(&self) -> bool {
+        match self.severity {
+            Severity::Error | Severity::Bug => true,
+            Severity::Warning => false,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use super::{Diagnostic, Label};
+
+/// Renders a diagnostic the way rustc does: a header, the location of the
+/// primary label and every labelled source line with its labels underlined.
+pub fn render(diagnostic: &Diagnostic, file_name: &str, source: &str) -> String {
+    let mut output = format!("{}: {}\n", diagnostic.severity.name(), diagnostic.message);
+
+    let mut labels: Vec<&Label> = diagnostic.labels.iter().collect();
+    labels.sort_by_key(|label| (label.span.line, label.span.column));
+
+    let gutter = labels
+        .iter()
+        .map(|label| label.span.line)
+        .max()
+        .unwrap_or(0)
+        .to_string()
+        .len();
+    let padding = " ".repeat(gutter);
+
+    let location = diagnostic
+        .labels
+        .iter()
+        .find(|label| label.primary)
+        .or_else(|| labels.first().copied());
+
+    if let Some(label) = location {
+        output += &format!(
+            "{}--> {}:{}:{}\n",
+            padding, file_name, label.span.line, label.span.column
+        );
+        output += &format!("{} |\n", padding);
+    }
+
+    let lines: Vec<&str> = source.lines().collect();
+    let mut previous_line: Option<usize> = None;
+    for label in &labels {
+        let line = label.span.line;
+        let text = lines.get(line.saturating_sub(1)).copied().unwrap_or("");
+
+        if previous_line != Some(line) {
+            if let Some(previous) = previous_line {
+                if line > previous + 1 {
+                    output += "...\n";
+                }
+            }
+
+            output += &format!("{:>width$} | {}\n", line, text, width = gutter);
+            previous_line = Some(line);
+        }
+
+        // keep tabs so that the markers line up with the source line
+        let indent: String = text
+            .chars()
+            .take(label.span.column.saturating_sub(1))
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let marker = if label.primary { "^" } else { "-" };
+        let underline = marker.repeat(underline_length(label, text, source));
+
+        output += format!("{} | {}{} {}", padding, indent, underline, label.message).trim_end();
+        output += "\n";
+    }
+
+    if !labels.is_empty() && !diagnostic.notes.is_empty() {
+        output += &format!("{} |\n", padding);
+    }
+
+    // multi-line notes are indented to line up with their first line
+    let continuation = format!("\n{}         ", padding);
+    for note in &diagnostic.notes {
+        output += &format!(
+            "{} = note: {}\n",
+            padding,
+            note.replace('\n', &continuation)
+        );
+    }
+
+    output
+}
+
+/// Spans can run over multiple lines, only the part on the first line is
+/// underlined. Empty spans, like the end of the file, still get one marker.
+fn underline_length(label: &Label, text: &str, source: &str) -> usize {
+    let length = source
+        .get(label.span.start..label.span.end)
+        .unwrap_or("")
+        .chars()
+        .take_while(|c| *c != '\n')
+        .count();
+    let remaining = text
+        .chars()
+        .count()
+        .saturating_sub(label.span.column.saturating_sub(1));
+
+    length.min(remaining).max(1)
+}
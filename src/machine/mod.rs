@@ -37,6 +37,8 @@ pub fn compile_to_current_target(
         LLVMCodeGenOptLevel::LLVMCodeGenLevelAggressive
     };
 
+    // position independent code, so objects that refer to constant data can be
+    // linked into the PIE executables most toolchains produce by default
     let target_machine = unsafe {
         LLVMCreateTargetMachine(
             target,
@@ -44,7 +46,7 @@ pub fn compile_to_current_target(
             raw_cstr("generic"),
             raw_cstr(""),
             optimisation_level,
-            LLVMRelocMode::LLVMRelocDefault,
+            LLVMRelocMode::LLVMRelocPIC,
             LLVMCodeModel::LLVMCodeModelDefault,
         )
     };
@@ -57,12 +59,6 @@ pub fn compile_to_current_target(
 
         let file = std::ffi::CString::new(output_file).unwrap().into_raw();
 
-        LLVMTargetMachineEmitToFile(
-            target_machine,
-            *module,
-            file as *mut i8,
-            file_type,
-            &mut error,
-        );
+        LLVMTargetMachineEmitToFile(target_machine, *module, file, file_type, &mut error);
     };
 }
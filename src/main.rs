@@ -7,12 +7,16 @@ use std::ffi::CString;
 use std::fs;
 use std::os::raw::c_char;
 use std::path;
+use std::process;
 use std::time::Instant;
 
 mod codegen;
+mod diagnostics;
 mod machine;
 mod parser;
+mod sema;
 
+use self::diagnostics::Diagnostic;
 use self::parser::parser::Parser;
 use self::parser::tokenizer::Tokenizer;
 
@@ -45,7 +49,16 @@ fn main() {
                 .help("Output format. Tokens, AST and LL are printed to stdout.")
                 .default_value("object"),
         )
-        .arg(Arg::with_name("debug").long("debug").help("Whether to perform optimisations"))
+        .arg(
+            Arg::with_name("debug")
+                .long("debug")
+                .help("Build without optimisations and panic when integer arithmetic overflows"),
+        )
+        .arg(
+            Arg::with_name("verify-only")
+                .long("verify-only")
+                .help("Check the program and verify the generated IR without writing any output"),
+        )
         .get_matches();
 
     let now = Instant::now();
@@ -58,22 +71,45 @@ fn main() {
         tokenizer.parse();
 
         if matches.value_of("file-type").unwrap_or("object") == "tokens" {
+            report_diagnostics(&tokenizer.diagnostics, file, &string);
             println!("{:#?}", tokenizer.tokens);
             return;
         }
 
+        // the tokenizer skips what it cannot read, so the syntax errors in the
+        // rest of the file are reported in the same run
         let mut parser = Parser::new(&tokenizer.tokens);
         parser.parse_module();
+        parser.module.file_name = file.to_string();
+
+        let mut diagnostics = tokenizer.diagnostics;
+        diagnostics.append(&mut parser.diagnostics);
+        diagnostics.sort_by_key(|diagnostic| diagnostic.primary_span().map(|span| span.start));
+        let has_errors = report_diagnostics(&diagnostics, file, &string);
 
+        // the AST is printed even when it is incomplete, so that it can be
+        // inspected while fixing syntax errors
         if matches.value_of("file-type").unwrap_or("object") == "ast" {
             println!("{:#?}", parser.module);
+        }
+
+        if has_errors {
+            process::exit(1);
+        }
+
+        if matches.value_of("file-type").unwrap_or("object") == "ast" {
             return;
         }
 
+        let diagnostics = sema::analyse(&mut parser.module);
+        if report_diagnostics(&diagnostics, file, &string) {
+            process::exit(1);
+        }
+
         let extension = match matches.value_of("file-type").unwrap_or("object") {
             "asm" => "s",
             "bc" => "bc",
-            "object" | _ => "o",
+            _ => "o",
         };
 
         let default_output_path = path::Path::new(file).with_extension(extension);
@@ -83,7 +119,18 @@ fn main() {
                 .expect("Invalid default output"),
         );
 
-        let llvm_module = codegen::compile_application(parser.module);
+        let llvm_module = match codegen::compile_application(parser.module, debug) {
+            Ok(llvm_module) => llvm_module,
+            Err(diagnostics) => {
+                report_diagnostics(&diagnostics, file, &string);
+                process::exit(1);
+            }
+        };
+
+        if matches.is_present("verify-only") {
+            println!("Verified {} in {}ms!", file, now.elapsed().as_millis());
+            return;
+        }
 
         if matches.value_of("file-type").unwrap_or("object") == "ll" {
             unsafe { llvm_sys::core::LLVMDumpModule(llvm_module) };
@@ -99,7 +146,7 @@ fn main() {
 
         let file_type = match matches.value_of("file-type").unwrap_or("object") {
             "asm" => llvm_sys::target_machine::LLVMCodeGenFileType::LLVMAssemblyFile,
-            "object" | _ => llvm_sys::target_machine::LLVMCodeGenFileType::LLVMObjectFile,
+            _ => llvm_sys::target_machine::LLVMCodeGenFileType::LLVMObjectFile,
         };
 
         machine::compile_to_current_target(&llvm_module, output, file_type, debug);
@@ -111,6 +158,24 @@ fn main() {
     }
 }
 
+/// Prints diagnostics to stderr, returns whether any of them is an error.
+fn report_diagnostics(diagnostics: &[Diagnostic], file: &str, source: &str) -> bool {
+    for diagnostic in diagnostics {
+        eprintln!("{}", diagnostics::render(diagnostic, file, source));
+    }
+
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    if errors > 0 {
+        eprintln!(
+            "error: aborting due to {} previous error{}",
+            errors,
+            if errors == 1 { "" } else { "s" }
+        );
+    }
+
+    errors > 0
+}
+
 fn raw_cstr(string: &str) -> *const c_char {
     let string = CString::new(string).expect("Cstring::new failed");
     string.into_raw()
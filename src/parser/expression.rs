@@ -1,120 +1,441 @@
-use super::tokenizer::BinaryOperator;
+use super::tokenizer::{BinaryOperator, UnaryOperator};
+use super::{Span, Type};
 
-#[derive(Debug)]
+/// Integers are wide enough for both the negative and the unsigned extremes
+/// of every integer type.
+#[derive(Clone, Copy, Debug)]
+pub enum NumberValue {
+    Integer(i128),
+    Float(f64),
+}
+
+#[derive(Clone, Debug)]
 pub struct NumberLiteralExpression {
-    pub number: f64,
+    pub number: NumberValue,
+    pub span: Span,
+    pub ty: Type,
 }
 
 impl NumberLiteralExpression {
-    pub fn new(number: f64) -> Self {
-        NumberLiteralExpression { number }
+    /// `ty` is the type given by the literal's suffix, if any.
+    pub fn new(number: NumberValue, ty: Type, span: Span) -> Self {
+        NumberLiteralExpression { number, span, ty }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+pub struct BooleanLiteralExpression {
+    pub value: bool,
+    pub span: Span,
+}
+
+impl BooleanLiteralExpression {
+    pub fn new(value: bool, span: Span) -> Self {
+        BooleanLiteralExpression { value, span }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StringLiteralExpression {
+    pub value: String,
+    pub span: Span,
+}
+
+impl StringLiteralExpression {
+    pub fn new(value: String, span: Span) -> Self {
+        StringLiteralExpression { value, span }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct VariableExpression {
     pub binding: String,
+    pub span: Span,
+    pub ty: Type,
 }
 
 impl VariableExpression {
-    pub fn new(binding: String) -> Self {
-        VariableExpression { binding }
+    pub fn new(binding: String, span: Span) -> Self {
+        VariableExpression {
+            binding,
+            span,
+            ty: Type::Unknown,
+        }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct BlockExpression {
     pub expressions: Vec<Expression>,
+    pub span: Span,
 }
 
 impl BlockExpression {
-    pub fn new(expressions: Vec<Expression>) -> Self {
-        BlockExpression { expressions }
+    pub fn new(expressions: Vec<Expression>, span: Span) -> Self {
+        BlockExpression { expressions, span }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct FunctionExpression {
     pub name: String,
     pub body: BlockExpression,
     pub arguments: Vec<FunctionArgument>,
+    pub return_type: Type,
+    pub span: Span,
+    pub name_span: Span,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ExternFunctionExpression {
     pub name: String,
     pub arguments: Vec<FunctionArgument>,
+    pub return_type: Type,
+    pub span: Span,
+    pub name_span: Span,
 }
 
 #[derive(Clone, Debug)]
 pub struct FunctionArgument {
     pub binding_name: String,
+    pub ty: Type,
+    pub span: Span,
 }
 
 impl FunctionArgument {
-    pub fn new(binding_name: String) -> Self {
-        Self { binding_name }
+    pub fn new(binding_name: String, ty: Type, span: Span) -> Self {
+        Self {
+            binding_name,
+            ty,
+            span,
+        }
     }
 }
 
 impl FunctionExpression {
-    pub fn new(name: &str, body: BlockExpression, args: Vec<FunctionArgument>) -> Self {
+    /// `return_type` is `Unknown` unless it was annotated, sema infers it
+    /// from the body in that case.
+    pub fn new(
+        name: &str,
+        body: BlockExpression,
+        args: Vec<FunctionArgument>,
+        return_type: Type,
+        span: Span,
+        name_span: Span,
+    ) -> Self {
         Self {
             name: String::from(name),
             arguments: args,
+            return_type,
             body,
+            span,
+            name_span,
         }
     }
 }
 
 impl ExternFunctionExpression {
-    pub fn new(name: &str, args: Vec<FunctionArgument>) -> Self {
+    pub fn new(
+        name: &str,
+        args: Vec<FunctionArgument>,
+        return_type: Type,
+        span: Span,
+        name_span: Span,
+    ) -> Self {
         Self {
             name: String::from(name),
             arguments: args,
+            return_type,
+            span,
+            name_span,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct CallExpression {
     pub function_name: String,
     pub args: Vec<Expression>,
+    pub span: Span,
+    pub ty: Type,
+    /// The types sema instantiated the type variables of a generic callee
+    /// with, empty when the callee is not generic at the call.
+    pub instance: Vec<Type>,
 }
 
 impl CallExpression {
-    pub fn new(function_name: String, args: Vec<Expression>) -> Self {
+    pub fn new(function_name: String, args: Vec<Expression>, span: Span) -> Self {
         Self {
             function_name,
             args,
+            span,
+            ty: Type::Unknown,
+            instance: Vec::new(),
         }
     }
 }
 
-#[derive(Debug)]
+/// `if condition do ... else ... end`, the `else` branch is optional.
+#[derive(Clone, Debug)]
+pub struct IfExpression {
+    pub condition: Expression,
+    pub then_block: BlockExpression,
+    pub else_block: Option<BlockExpression>,
+    pub span: Span,
+    pub ty: Type,
+}
+
+impl IfExpression {
+    pub fn new(
+        condition: Expression,
+        then_block: BlockExpression,
+        else_block: Option<BlockExpression>,
+        span: Span,
+    ) -> Self {
+        IfExpression {
+            condition,
+            then_block,
+            else_block,
+            span,
+            ty: Type::Unknown,
+        }
+    }
+}
+
+/// `while condition do ... end`, which always evaluates to zero.
+#[derive(Clone, Debug)]
+pub struct WhileExpression {
+    pub condition: Expression,
+    pub body: BlockExpression,
+    pub span: Span,
+}
+
+impl WhileExpression {
+    pub fn new(condition: Expression, body: BlockExpression, span: Span) -> Self {
+        WhileExpression {
+            condition,
+            body,
+            span,
+        }
+    }
+}
+
+/// `break` or `continue`, leaving the innermost loop or starting its next
+/// iteration.
+#[derive(Clone, Debug)]
+pub struct LoopControlExpression {
+    pub is_break: bool,
+    pub span: Span,
+}
+
+impl LoopControlExpression {
+    pub fn new(is_break: bool, span: Span) -> Self {
+        LoopControlExpression { is_break, span }
+    }
+
+    pub fn keyword(&self) -> &'static str {
+        if self.is_break {
+            "break"
+        } else {
+            "continue"
+        }
+    }
+}
+
+/// `return value`, leaving the function with `value`. The value is left out
+/// in functions that return `void`.
+#[derive(Clone, Debug)]
+pub struct ReturnExpression {
+    pub value: Option<Expression>,
+    pub span: Span,
+}
+
+impl ReturnExpression {
+    pub fn new(value: Option<Expression>, span: Span) -> Self {
+        ReturnExpression { value, span }
+    }
+}
+
+/// `let name = value` or, when `mutable`, `var name = value`. The binding is
+/// visible in the rest of the enclosing block.
+#[derive(Clone, Debug)]
+pub struct LetExpression {
+    pub binding: String,
+    pub mutable: bool,
+    pub annotation: Type,
+    pub value: Expression,
+    pub span: Span,
+    pub binding_span: Span,
+    pub ty: Type,
+}
+
+impl LetExpression {
+    pub fn new(
+        binding: String,
+        mutable: bool,
+        annotation: Type,
+        value: Expression,
+        span: Span,
+        binding_span: Span,
+    ) -> Self {
+        LetExpression {
+            binding,
+            mutable,
+            annotation,
+            value,
+            span,
+            binding_span,
+            ty: Type::Unknown,
+        }
+    }
+}
+
+/// `name = value`, which only compiles when `name` was declared with `var`.
+#[derive(Clone, Debug)]
+pub struct AssignExpression {
+    pub binding: String,
+    pub value: Expression,
+    pub span: Span,
+    pub binding_span: Span,
+    pub ty: Type,
+}
+
+impl AssignExpression {
+    pub fn new(binding: String, value: Expression, span: Span, binding_span: Span) -> Self {
+        AssignExpression {
+            binding,
+            value,
+            span,
+            binding_span,
+            ty: Type::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct UnaryExpression {
+    pub operator: UnaryOperator,
+    pub operand: Expression,
+    pub span: Span,
+    pub ty: Type,
+}
+
+impl UnaryExpression {
+    pub fn new(operator: UnaryOperator, operand: Expression, span: Span) -> Self {
+        UnaryExpression {
+            operator,
+            operand,
+            span,
+            ty: Type::Unknown,
+        }
+    }
+}
+
+/// `value as type`, converting a number to another numeric type.
+#[derive(Clone, Debug)]
+pub struct CastExpression {
+    pub value: Expression,
+    pub target: Type,
+    pub span: Span,
+}
+
+impl CastExpression {
+    pub fn new(value: Expression, target: Type, span: Span) -> Self {
+        CastExpression {
+            value,
+            target,
+            span,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct BinaryExpression {
     pub left: Expression,
     pub right: Expression,
     pub operator: BinaryOperator,
+    pub span: Span,
+    pub ty: Type,
 }
 
 impl BinaryExpression {
     pub fn new(operator: BinaryOperator, left: Expression, right: Expression) -> Self {
+        let span = left.span().to(right.span());
+
         BinaryExpression {
             left,
             right,
             operator,
+            span,
+            ty: Type::Unknown,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Expression {
     Function(Box<FunctionExpression>),
     ExternFunction(Box<ExternFunctionExpression>),
     Binary(Box<BinaryExpression>),
+    Unary(Box<UnaryExpression>),
+    Cast(Box<CastExpression>),
     NumberLiteral(Box<NumberLiteralExpression>),
+    BooleanLiteral(Box<BooleanLiteralExpression>),
+    StringLiteral(Box<StringLiteralExpression>),
     Variable(Box<VariableExpression>),
     Call(Box<CallExpression>),
+    If(Box<IfExpression>),
+    While(Box<WhileExpression>),
+    LoopControl(Box<LoopControlExpression>),
+    Return(Box<ReturnExpression>),
+    Let(Box<LetExpression>),
+    Assign(Box<AssignExpression>),
     // Block(Box<BlockExpression>),
 }
+
+impl Expression {
+    pub fn span(&self) -> Span {
+        use Expression::*;
+
+        match self {
+            Function(expr) => expr.span,
+            ExternFunction(expr) => expr.span,
+            Binary(expr) => expr.span,
+            Unary(expr) => expr.span,
+            Cast(expr) => expr.span,
+            NumberLiteral(expr) => expr.span,
+            BooleanLiteral(expr) => expr.span,
+            StringLiteral(expr) => expr.span,
+            Variable(expr) => expr.span,
+            Call(expr) => expr.span,
+            If(expr) => expr.span,
+            While(expr) => expr.span,
+            LoopControl(expr) => expr.span,
+            Return(expr) => expr.span,
+            Let(expr) => expr.span,
+            Assign(expr) => expr.span,
+        }
+    }
+
+    /// The type sema assigned to this expression, declarations have none.
+    pub fn ty(&self) -> Type {
+        use Expression::*;
+
+        match self {
+            Function(_) | ExternFunction(_) => Type::Unknown,
+            Binary(expr) => expr.ty,
+            Unary(expr) => expr.ty,
+            Cast(expr) => expr.target,
+            NumberLiteral(expr) => expr.ty,
+            BooleanLiteral(_) => Type::Bool,
+            StringLiteral(_) => Type::Ptr,
+            Variable(expr) => expr.ty,
+            Call(expr) => expr.ty,
+            If(expr) => expr.ty,
+            While(_) => Type::I64,
+            LoopControl(_) | Return(_) => Type::Never,
+            Let(expr) => expr.ty,
+            Assign(expr) => expr.ty,
+        }
+    }
+}
@@ -1,11 +1,18 @@
 pub mod expression;
 pub mod module;
+#[allow(clippy::module_inception)]
 pub mod parser;
+pub mod span;
 pub mod tokenizer;
+pub mod types;
 
 pub use expression::{
-    BinaryExpression, BlockExpression, CallExpression, Expression, ExternFunctionExpression,
-    FunctionExpression, NumberLiteralExpression, VariableExpression,
+    AssignExpression, BinaryExpression, BlockExpression, BooleanLiteralExpression, CallExpression,
+    CastExpression, Expression, ExternFunctionExpression, FunctionExpression, IfExpression,
+    LetExpression, LoopControlExpression, NumberLiteralExpression, ReturnExpression,
+    StringLiteralExpression, UnaryExpression, VariableExpression, WhileExpression,
 };
 
 pub use module::Module;
+pub use span::Span;
+pub use types::Type;
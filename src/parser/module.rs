@@ -3,6 +3,8 @@ use super::Expression;
 #[derive(Debug)]
 pub struct Module {
     pub name: String,
+    /// The path of the source file, which runtime panics report.
+    pub file_name: String,
     pub expressions: Vec<Expression>,
 }
 
@@ -10,6 +12,7 @@ impl Module {
     pub fn new() -> Self {
         Self {
             name: String::from("Main"),
+            file_name: String::new(),
             expressions: Vec::new(),
         }
     }
@@ -1,106 +1,366 @@
 use super::expression::*;
-use super::tokenizer::{Keyword::*, Token, TokenValue, TokenValue::*};
-use super::Module;
+use super::tokenizer::{
+    BinaryOperator, Keyword::*, Token, TokenValue, TokenValue::*, UnaryOperator,
+};
+use super::types::NAMED_TYPES;
+use super::{Module, Span, Type};
+use crate::diagnostics::Diagnostic;
 
 use std::iter::Peekable;
 use std::slice::Iter;
 
+pub type ParseResult<T> = Result<T, Diagnostic>;
+
 #[derive(Debug)]
 pub struct Parser<'a> {
     tokens: Peekable<Iter<'a, Token>>,
+    previous_span: Span,
+    eof_span: Span,
     pub module: Module,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
-impl Parser<'_> {
-    pub fn new(tokens: &'_ Vec<Token>) -> Parser {
+impl<'a> Parser<'a> {
+    pub fn new(tokens: &'a [Token]) -> Parser<'a> {
+        // errors at the end of the file point just past the last token
+        let eof_span = tokens
+            .last()
+            .map(|token| {
+                let span = token.span;
+                Span::new(
+                    span.end,
+                    span.end,
+                    span.line,
+                    span.column + (span.end - span.start),
+                )
+            })
+            .unwrap_or_else(|| Span::new(0, 0, 1, 1));
+
         Parser {
             tokens: tokens.iter().peekable(),
+            previous_span: Span::default(),
+            eof_span,
             module: Module::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Consumes the next token, remembering its span so that the caller can
+    /// construct the span of the node it is building.
+    fn next_token(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.next()?;
+        self.previous_span = token.span;
+        Some(token)
+    }
+
+    fn peek(&mut self) -> Option<&'a Token> {
+        self.tokens.peek().copied()
+    }
+
+    /// Peeks the next token, `expected` describes what we were looking for
+    /// when the file ends prematurely.
+    fn peek_or_eof(&mut self, expected: &str) -> ParseResult<&'a Token> {
+        match self.peek() {
+            Some(token) => Ok(token),
+            None => Err(self.unexpected_eof(expected)),
+        }
+    }
+
+    fn expect(&mut self, value: TokenValue) -> ParseResult<&'a Token> {
+        let expected = value.to_string();
+        let token = self.peek_or_eof(&expected)?;
+
+        if token.value == value {
+            self.next_token();
+            Ok(token)
+        } else {
+            Err(unexpected(token, &expected))
+        }
+    }
+
+    fn expect_identifier(&mut self) -> ParseResult<(&'a String, Span)> {
+        let token = self.peek_or_eof("an identifier")?;
+
+        if let Identifier(name) = &token.value {
+            self.next_token();
+            Ok((name, token.span))
+        } else {
+            Err(unexpected(token, "an identifier"))
+        }
+    }
+
+    /// Skips tokens after a syntax error until a point where parsing can
+    /// resume: past the `end` closing the current block, or in front of a
+    /// `fn` or `defextern`, which always start a new top level item.
+    /// `depth` is the number of blocks we are currently nested in.
+    fn synchronise(&mut self, mut depth: usize) {
+        while let Some(token) = self.peek() {
+            match token.value {
+                Keyword(Fn) | Keyword(DefExtern) => return,
+                Keyword(Do) => depth += 1,
+                Keyword(End) if depth <= 1 => {
+                    self.next_token();
+                    return;
+                }
+                Keyword(End) => depth -= 1,
+                _ => {}
+            }
+
+            self.next_token();
         }
     }
 
-    pub fn parse_expression(&mut self) -> Expression {
-        let atom = self.parse_atom();
-        let maybe_binary_expression = self.parse_maybe_binary(atom, 0);
+    fn unexpected_eof(&self, expected: &str) -> Diagnostic {
+        Diagnostic::error(format!("expected {}, found end of file", expected))
+            .with_primary(self.eof_span, format!("expected {}", expected))
+    }
+
+    pub fn parse_expression(&mut self) -> ParseResult<Expression> {
+        let atom = self.parse_operand()?;
+        let maybe_binary_expression = self.parse_maybe_binary(atom, 0)?;
 
         if let Expression::Variable(variable_expression) = &maybe_binary_expression {
-            if let Some(expression) = self.parse_maybe_call(&variable_expression) {
-                return expression;
+            if let Some(expression) = self.parse_maybe_assign(variable_expression)? {
+                return Ok(expression);
             }
         }
 
-        return maybe_binary_expression;
+        Ok(maybe_binary_expression)
     }
 
-    pub fn parse_maybe_call(&mut self, expression: &VariableExpression) -> Option<Expression> {
-        if let TokenValue::OpenParen = self.tokens.peek().unwrap().value {
-            self.tokens.by_ref().next();
+    /// Parses any number of argument lists following `callee`, so calls
+    /// compose with every other expression. The `(` has to be on the line
+    /// the callee ends on, otherwise it starts a parenthesised expression.
+    fn parse_postfix(&mut self, mut callee: Expression) -> ParseResult<Expression> {
+        while let Some(token) = self.peek() {
+            if token.value != OpenParen || token.span.line != self.previous_span.line {
+                break;
+            }
 
-            let mut call_args: Vec<Expression> = vec![];
-            while self.tokens.by_ref().peek().is_some() {
-                let possible_end_token = self.tokens.by_ref().peek().expect("Unexpected EOF");
-                if let CloseParen = &possible_end_token.value {
-                    self.tokens.by_ref().next();
-                    break;
+            let function_name = match &callee {
+                Expression::Variable(variable) => variable.binding.clone(),
+                _ => {
+                    return Err(Diagnostic::error("expected a function name")
+                        .with_primary(callee.span(), "this cannot be called")
+                        .with_secondary(token.span, "call starts here")
+                        .with_note("only functions can be called, by their name"))
                 }
+            };
 
-                let call_arg_expression = self.parse_expression();
-                call_args.push(call_arg_expression);
-
-                let _ = {
-                    let delimiter = self.tokens.by_ref().peek().expect("Unexpected EOF");
-                    if let Comma = &delimiter.value {
-                        self.tokens.by_ref().next()
-                    } else if let CloseParen = &delimiter.value {
-                        self.tokens.by_ref().next();
-                        break;
-                    } else {
-                        panic!("expected `,`, got {:?}", delimiter.value)
-                    }
-                };
-            }
+            self.next_token();
+            let call_args = self.parse_call_args(token.span)?;
 
-            return Some(Expression::Call(Box::new(CallExpression::new(
-                expression.binding.clone(),
+            callee = Expression::Call(Box::new(CallExpression::new(
+                function_name,
                 call_args,
-            ))));
+                callee.span().to(self.previous_span),
+            )));
         }
 
-        return None;
+        Ok(callee)
+    }
+
+    /// Parses `= value` after a variable. Assignment binds the loosest, so
+    /// the value is a whole expression, which may be another assignment.
+    fn parse_maybe_assign(
+        &mut self,
+        expression: &VariableExpression,
+    ) -> ParseResult<Option<Expression>> {
+        match self.peek() {
+            Some(token) if token.value == Assign => {
+                self.next_token();
+
+                let value = self.parse_expression()?;
+                let span = expression.span.to(value.span());
+
+                Ok(Some(Expression::Assign(Box::new(AssignExpression::new(
+                    expression.binding.clone(),
+                    value,
+                    span,
+                    expression.span,
+                )))))
+            }
+            _ => Ok(None),
+        }
     }
 
-    pub fn parse_atom(&mut self) -> Expression {
-        let peek = self.tokens.peek().expect("Unexpected EOF");
+    fn parse_call_args(&mut self, open_span: Span) -> ParseResult<Vec<Expression>> {
+        let mut call_args: Vec<Expression> = vec![];
+
+        loop {
+            let possible_end_token = self
+                .peek_or_eof("`)`")
+                .map_err(|d| d.with_secondary(open_span, "unclosed argument list"))?;
+            if let CloseParen = &possible_end_token.value {
+                self.next_token();
+                break;
+            }
+
+            let call_arg_expression = self.parse_expression()?;
+            call_args.push(call_arg_expression);
+
+            let delimiter = self
+                .peek_or_eof("`,` or `)`")
+                .map_err(|d| d.with_secondary(open_span, "unclosed argument list"))?;
+            if let Comma = &delimiter.value {
+                self.next_token();
+            } else if let CloseParen = &delimiter.value {
+                self.next_token();
+                break;
+            } else {
+                return Err(unexpected(delimiter, "`,` or `)`")
+                    .with_secondary(open_span, "argument list starts here"));
+            }
+        }
+
+        Ok(call_args)
+    }
+
+    /// An operand of a binary operator: an atom followed by any number of
+    /// `as type` casts, which bind tighter than every binary operator.
+    fn parse_operand(&mut self) -> ParseResult<Expression> {
+        let mut operand = self.parse_atom()?;
+
+        while let Some(Token {
+            value: Keyword(As), ..
+        }) = self.peek()
+        {
+            self.next_token();
+            let target = self.parse_value_type()?;
+
+            let span = operand.span().to(self.previous_span);
+            operand = Expression::Cast(Box::new(CastExpression::new(operand, target, span)));
+        }
+
+        Ok(operand)
+    }
+
+    /// Parses a primary expression followed by any calls on it.
+    pub fn parse_atom(&mut self) -> ParseResult<Expression> {
+        let primary = self.parse_primary()?;
+        self.parse_postfix(primary)
+    }
+
+    fn parse_primary(&mut self) -> ParseResult<Expression> {
+        let peek = self.peek_or_eof("an expression")?;
+        let span = peek.span;
         match &peek.value {
-            TokenValue::NumConst(float) => {
-                self.tokens.by_ref().next();
-                Expression::NumberLiteral(Box::new(NumberLiteralExpression::new(*float)))
+            TokenValue::IntConst(integer, ty) => {
+                self.next_token();
+                Ok(Expression::NumberLiteral(Box::new(
+                    NumberLiteralExpression::new(NumberValue::Integer(*integer as i128), *ty, span),
+                )))
+            }
+            TokenValue::FloatConst(float, ty) => {
+                self.next_token();
+                Ok(Expression::NumberLiteral(Box::new(
+                    NumberLiteralExpression::new(NumberValue::Float(*float), *ty, span),
+                )))
+            }
+            TokenValue::StringConst(string) => {
+                self.next_token();
+                Ok(Expression::StringLiteral(Box::new(
+                    StringLiteralExpression::new(string.clone(), span),
+                )))
+            }
+            TokenValue::Keyword(keyword @ (True | False)) => {
+                self.next_token();
+                Ok(Expression::BooleanLiteral(Box::new(
+                    BooleanLiteralExpression::new(*keyword == True, span),
+                )))
             }
+            TokenValue::OpenParen => self.parse_parenthesised(),
+            TokenValue::Operator(BinaryOperator::Sub) => {
+                self.next_token();
+                self.parse_unary(UnaryOperator::Neg, span)
+            }
+            TokenValue::UnaryOperator(operator) => {
+                self.next_token();
+                self.parse_unary(*operator, span)
+            }
+            TokenValue::Keyword(If) => Ok(Expression::If(Box::new(self.parse_if()?))),
+            TokenValue::Keyword(Let | Var) => Ok(Expression::Let(Box::new(self.parse_let()?))),
+            TokenValue::Keyword(While) => Ok(Expression::While(Box::new(self.parse_while()?))),
+            TokenValue::Keyword(keyword @ (Break | Continue)) => {
+                self.next_token();
+                Ok(Expression::LoopControl(Box::new(
+                    LoopControlExpression::new(*keyword == Break, span),
+                )))
+            }
+            TokenValue::Keyword(Return) => Ok(Expression::Return(Box::new(self.parse_return()?))),
             TokenValue::Identifier(identifier) => {
-                self.tokens.by_ref().next();
-                Expression::Variable(Box::new(VariableExpression::new(identifier.clone())))
+                self.next_token();
+                Ok(Expression::Variable(Box::new(VariableExpression::new(
+                    identifier.clone(),
+                    span,
+                ))))
             }
-            val => panic!(
-                "Expected an expression, got token {:?} which cannot compose an expression",
-                val
-            ),
+            _ => Err(unexpected(peek, "an expression")),
+        }
+    }
+
+    fn parse_parenthesised(&mut self) -> ParseResult<Expression> {
+        let open_span = self.expect(OpenParen)?.span;
+        let expression = self.parse_expression()?;
+
+        let token = self
+            .peek_or_eof("`)`")
+            .map_err(|d| d.with_secondary(open_span, "unclosed delimiter"))?;
+        if token.value != CloseParen {
+            return Err(unexpected(token, "`)`").with_secondary(open_span, "unclosed delimiter"));
         }
+        self.next_token();
+
+        Ok(expression)
     }
 
-    pub fn parse_maybe_binary(&mut self, left: Expression, precedence: u16) -> Expression {
-        let peek = self.tokens.peek().expect("Unexpected EOF");
+    /// Parses the operand of a prefix operator, which binds tighter than any
+    /// binary operator. Negated number literals are folded into a negative
+    /// literal, so that the minimum value of a type can be written.
+    fn parse_unary(
+        &mut self,
+        operator: UnaryOperator,
+        operator_span: Span,
+    ) -> ParseResult<Expression> {
+        let operand = self.parse_atom()?;
+        let span = operator_span.to(operand.span());
+
+        if let (UnaryOperator::Neg, Expression::NumberLiteral(literal)) = (operator, &operand) {
+            let number = match literal.number {
+                NumberValue::Integer(integer) => NumberValue::Integer(-integer),
+                NumberValue::Float(float) => NumberValue::Float(-float),
+            };
 
-        if let Operator(op) = &peek.value {
+            return Ok(Expression::NumberLiteral(Box::new(
+                NumberLiteralExpression::new(number, literal.ty, span),
+            )));
+        }
+
+        Ok(Expression::Unary(Box::new(UnaryExpression::new(
+            operator, operand, span,
+        ))))
+    }
+
+    pub fn parse_maybe_binary(
+        &mut self,
+        left: Expression,
+        precedence: u16,
+    ) -> ParseResult<Expression> {
+        if let Some(Token {
+            value: Operator(op),
+            ..
+        }) = self.peek()
+        {
             let right_precedence = op.precedence();
 
             if right_precedence > precedence {
-                let _token = self.tokens.by_ref().next();
+                let _token = self.next_token();
 
-                let expression = self.parse_atom();
+                let expression = self.parse_operand()?;
                 let binary_expression = BinaryExpression::new(
                     *op,
                     left,
-                    self.parse_maybe_binary(expression, right_precedence),
+                    self.parse_maybe_binary(expression, right_precedence)?,
                 );
 
                 return self.parse_maybe_binary(
@@ -110,154 +370,440 @@ impl Parser<'_> {
             }
         }
 
-        return left;
+        Ok(left)
     }
 
-    pub fn parse_block(&mut self) -> BlockExpression {
-        let open_token = self.tokens.by_ref().peek().expect("Unexpected EOF");
-        if let Keyword(Do) = &open_token.value {
-            self.tokens.by_ref().next();
-        } else {
-            panic!("Expected token Keyword(Do), got {:?}", open_token)
-        }
+    pub fn parse_block(&mut self) -> ParseResult<BlockExpression> {
+        let start_span = self.expect(Keyword(Do))?.span;
+        let (block, _) = self.parse_block_body(start_span, false)?;
 
-        let mut expressions = Vec::new();
+        Ok(block)
+    }
 
-        while self.tokens.peek().is_some() {
-            let possible_end_token = self.tokens.by_ref().peek().expect("Unexpected EOF");
-            if let Keyword(End) = &possible_end_token.value {
-                self.tokens.by_ref().next();
-                break;
+    /// Parses expressions up to and including the `end` closing a block that
+    /// started at `start_span`. With `until_else` the block may also be ended
+    /// by an `else`, which is left for the caller and reported by returning
+    /// `true`.
+    fn parse_block_body(
+        &mut self,
+        start_span: Span,
+        until_else: bool,
+    ) -> ParseResult<(BlockExpression, bool)> {
+        let mut expressions = Vec::new();
+        let mut ended_at_else = false;
+
+        loop {
+            let possible_end_token = self
+                .peek_or_eof("`end`")
+                .map_err(|d| d.with_secondary(start_span, "block starts here"))?;
+            match &possible_end_token.value {
+                Keyword(End) => {
+                    self.next_token();
+                    break;
+                }
+                Keyword(Else) if until_else => {
+                    ended_at_else = true;
+                    break;
+                }
+                // a new top level item means this block was never closed,
+                // leave it for the module to parse
+                Keyword(Fn) | Keyword(DefExtern) => {
+                    self.diagnostics.push(
+                        unexpected(possible_end_token, "`end`")
+                            .with_secondary(start_span, "block starts here"),
+                    );
+                    break;
+                }
+                _ => {}
             }
 
-            let expression = self.parse_expression();
-            expressions.push(expression);
+            match self.parse_expression() {
+                Ok(expression) => expressions.push(expression),
+                Err(diagnostic) => {
+                    self.diagnostics.push(diagnostic);
+                    self.synchronise(1);
+                    break;
+                }
+            }
         }
 
-        return BlockExpression::new(expressions);
+        let block = BlockExpression::new(expressions, start_span.to(self.previous_span));
+        Ok((block, ended_at_else))
     }
 
-    pub fn parse_function(&mut self) -> FunctionExpression {
-        let fn_keyword = self.tokens.by_ref().peek().expect("Unexpected EOF");
+    /// Parses `if condition do ... [else ...] end`. An `else` directly
+    /// followed by another `if` chains them, sharing the last `end`.
+    fn parse_if(&mut self) -> ParseResult<IfExpression> {
+        let start_span = self.expect(Keyword(If))?.span;
+        let condition = self.parse_expression()?;
+
+        let do_span = self.expect(Keyword(Do))?.span;
+        let (then_block, has_else) = self.parse_block_body(do_span, true)?;
+
+        let else_block = if has_else {
+            let else_span = self.expect(Keyword(Else))?.span;
+
+            match self.peek() {
+                Some(token) if token.value == Keyword(If) => {
+                    let nested = self.parse_if()?;
+                    let span = else_span.to(nested.span);
+                    Some(BlockExpression::new(
+                        vec![Expression::If(Box::new(nested))],
+                        span,
+                    ))
+                }
+                _ => Some(self.parse_block_body(else_span, false)?.0),
+            }
+        } else {
+            None
+        };
 
-        if let Keyword(Fn) = &fn_keyword.value {
-            self.tokens.by_ref().next();
+        Ok(IfExpression::new(
+            condition,
+            then_block,
+            else_block,
+            start_span.to(self.previous_span),
+        ))
+    }
 
-            let name_token = self.tokens.by_ref().peek().expect("Unexpected EOF");
-            if let Identifier(name) = &name_token.value {
-                self.tokens.by_ref().next();
+    /// Parses `let name = value` and `var name = value`, both with an
+    /// optional `: type` annotation after the name.
+    fn parse_let(&mut self) -> ParseResult<LetExpression> {
+        let token = self.peek_or_eof("`let` or `var`")?;
+        let mutable = match token.value {
+            Keyword(Let) => false,
+            Keyword(Var) => true,
+            _ => return Err(unexpected(token, "`let` or `var`")),
+        };
+        self.next_token();
 
-                let peek = self.tokens.by_ref().peek().expect("Unexpected EOF");
-                let args = if let TokenValue::OpenParen = peek.value {
-                    self.parse_function_args()
-                } else {
-                    Vec::new()
-                };
+        let (binding, binding_span) = self.expect_identifier()?;
 
-                FunctionExpression::new(name, self.parse_block(), args)
-            } else {
-                panic!("Unexpected token, expected Identifier ")
+        let annotation = match self.peek() {
+            Some(token) if token.value == Colon => {
+                self.next_token();
+                self.parse_value_type()?
             }
-        } else {
-            panic!("Unexpected token, expected Keyword `fn` ")
-        }
+            _ => Type::Unknown,
+        };
+
+        self.expect(Assign)?;
+        let value = self.parse_expression()?;
+        let span = token.span.to(value.span());
+
+        Ok(LetExpression::new(
+            binding.clone(),
+            mutable,
+            annotation,
+            value,
+            span,
+            binding_span,
+        ))
     }
 
-    pub fn parse_extern_function(&mut self) -> ExternFunctionExpression {
-        let def_extern_keyword = self.tokens.by_ref().peek().expect("Unexpected EOF");
+    /// Parses `return` with an optional value, which has to start on the
+    /// same line as the `return`.
+    fn parse_return(&mut self) -> ParseResult<ReturnExpression> {
+        let start_span = self.expect(Keyword(Return))?.span;
+
+        let value = match self.peek() {
+            Some(token)
+                if token.span.line == start_span.line
+                    && !matches!(
+                        token.value,
+                        Keyword(End) | Keyword(Else) | CloseParen | Comma
+                    ) =>
+            {
+                Some(self.parse_expression()?)
+            }
+            _ => None,
+        };
 
-        if let Keyword(DefExtern) = &def_extern_keyword.value {
-            self.tokens.by_ref().next();
+        Ok(ReturnExpression::new(
+            value,
+            start_span.to(self.previous_span),
+        ))
+    }
 
-            let name_token = self.tokens.by_ref().peek().expect("Unexpected EOF");
-            if let Identifier(name) = &name_token.value {
-                self.tokens.by_ref().next();
+    fn parse_while(&mut self) -> ParseResult<WhileExpression> {
+        let start_span = self.expect(Keyword(While))?.span;
+        let condition = self.parse_expression()?;
+        let body = self.parse_block()?;
 
-                let peek = self.tokens.by_ref().peek().expect("Unexpected EOF");
-                let args = if let TokenValue::OpenParen = peek.value {
-                    self.parse_function_args()
-                } else {
-                    Vec::new()
-                };
+        Ok(WhileExpression::new(
+            condition,
+            body,
+            start_span.to(self.previous_span),
+        ))
+    }
 
-                ExternFunctionExpression::new(name, args)
-            } else {
-                panic!("Unexpected token, expected Identifier ")
-            }
+    pub fn parse_function(&mut self) -> ParseResult<FunctionExpression> {
+        let start_span = self.expect(Keyword(Fn))?.span;
+        let (name, name_span) = self.expect_identifier()?;
+
+        let peek = self.peek_or_eof("`(`, `->` or `do`")?;
+        let args = if let TokenValue::OpenParen = peek.value {
+            self.parse_function_args()?
         } else {
-            panic!("Unexpected token, expected Keyword `defextern` ")
-        }
+            Vec::new()
+        };
+
+        let return_type = self.parse_return_type()?;
+
+        let body = self.parse_block()?;
+        let span = start_span.to(body.span);
+        Ok(FunctionExpression::new(
+            name,
+            body,
+            args,
+            return_type,
+            span,
+            name_span,
+        ))
     }
 
-    fn parse_function_args(&mut self) -> Vec<FunctionArgument> {
-        let open_paren = self.tokens.by_ref().peek().expect("Unexpected EOF");
-        if let OpenParen = &open_paren.value {
-            self.tokens.by_ref().next();
-        } else {
-            panic!("expected `(`, got {:?}", open_paren.value)
-        }
+    pub fn parse_extern_function(&mut self) -> ParseResult<ExternFunctionExpression> {
+        let start_span = self.expect(Keyword(DefExtern))?.span;
+        let (name, name_span) = self.expect_identifier()?;
+
+        let args = match self.peek() {
+            Some(token) if token.value == OpenParen => self.parse_function_args()?,
+            _ => Vec::new(),
+        };
+
+        let return_type = self.parse_return_type()?;
+
+        Ok(ExternFunctionExpression::new(
+            name,
+            args,
+            return_type,
+            start_span.to(self.previous_span),
+            name_span,
+        ))
+    }
+
+    fn parse_function_args(&mut self) -> ParseResult<Vec<FunctionArgument>> {
+        let open_span = self.expect(OpenParen)?.span;
 
         let mut args: Vec<FunctionArgument> = vec![];
-        while self.tokens.by_ref().peek().is_some() {
-            let possible_end_token = self.tokens.by_ref().peek().expect("Unexpected EOF");
+        loop {
+            let possible_end_token = self
+                .peek_or_eof("`)`")
+                .map_err(|d| d.with_secondary(open_span, "unclosed argument list"))?;
             if let CloseParen = &possible_end_token.value {
-                self.tokens.by_ref().next();
+                self.next_token();
                 break;
             }
 
-            let fn_signature = self.parse_function_arg_signature();
+            let fn_signature = self.parse_function_arg_signature()?;
             args.push(fn_signature);
 
-            let _ = {
-                let delimiter = self.tokens.by_ref().peek().expect("Unexpected EOF");
-                if let Comma = &delimiter.value {
-                    self.tokens.by_ref().next()
-                } else if let CloseParen = &delimiter.value {
-                    self.tokens.by_ref().next();
-                    break;
-                } else {
-                    panic!("expected `,`, got {:?}", delimiter.value)
-                }
-            };
+            let delimiter = self
+                .peek_or_eof("`,` or `)`")
+                .map_err(|d| d.with_secondary(open_span, "unclosed argument list"))?;
+            if let Comma = &delimiter.value {
+                self.next_token();
+            } else if let CloseParen = &delimiter.value {
+                self.next_token();
+                break;
+            } else {
+                return Err(unexpected(delimiter, "`,` or `)`")
+                    .with_secondary(open_span, "argument list starts here"));
+            }
         }
 
-        args
+        Ok(args)
     }
 
-    fn parse_function_arg_signature(&mut self) -> FunctionArgument {
-        let peek = self.tokens.by_ref().peek().expect("Unexpected EOF");
-        match &peek.value {
-            Identifier(binding) => {
-                self.tokens.by_ref().next();
-                FunctionArgument::new(binding.clone())
+    fn parse_function_arg_signature(&mut self) -> ParseResult<FunctionArgument> {
+        let (binding, span) = self.expect_identifier()?;
+
+        let ty = match self.peek() {
+            Some(token) if token.value == Colon => {
+                self.next_token();
+                self.parse_value_type()?
+            }
+            _ => Type::Unknown,
+        };
+
+        Ok(FunctionArgument::new(binding.clone(), ty, span))
+    }
+
+    /// The optional `-> type` after the arguments of a function, `Unknown`
+    /// when it is left out.
+    fn parse_return_type(&mut self) -> ParseResult<Type> {
+        match self.peek() {
+            Some(token) if token.value == Arrow => {
+                self.next_token();
+                self.parse_type()
             }
-            token => panic!("Unexpected token {:?}", token),
+            _ => Ok(Type::Unknown),
+        }
+    }
+
+    /// A type for a binding, which can be anything but `void`.
+    fn parse_value_type(&mut self) -> ParseResult<Type> {
+        let ty = self.parse_type()?;
+
+        if ty == Type::Void {
+            return Err(Diagnostic::error("`void` is not a valid type for a value")
+                .with_primary(self.previous_span, "not allowed here")
+                .with_note("`void` can only be used as the return type of a function"));
         }
+
+        Ok(ty)
     }
 
-    pub fn parse_top_level_expression(&mut self) {
-        let peek = self
-            .tokens
-            .by_ref()
-            .peek()
-            .expect("Expected token, got None");
+    fn parse_type(&mut self) -> ParseResult<Type> {
+        let token = self.peek_or_eof("a type")?;
+
+        if let Identifier(name) = &token.value {
+            if let Some(ty) = Type::from_name(name) {
+                self.next_token();
+                return Ok(ty);
+            }
+
+            return Err(Diagnostic::error(format!("unknown type `{}`", name))
+                .with_primary(token.span, "not a type")
+                .with_note(format!(
+                    "the available types are {}",
+                    NAMED_TYPES
+                        .iter()
+                        .map(|ty| format!("`{}`", ty))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )));
+        }
+
+        Err(unexpected(token, "a type"))
+    }
+
+    pub fn parse_top_level_expression(&mut self) -> ParseResult<()> {
+        let peek = self.peek_or_eof("`fn` or `defextern`")?;
 
         let expression: Option<Expression> = match &peek.value {
-            Keyword(Fn) => Some(Expression::Function(Box::new(self.parse_function()))),
+            Keyword(Fn) => Some(Expression::Function(Box::new(self.parse_function()?))),
             Keyword(DefExtern) => Some(Expression::ExternFunction(Box::new(
-                self.parse_extern_function(),
+                self.parse_extern_function()?,
             ))),
-            token => panic!("Unexpected token, {:?}", token),
+            _ => {
+                return Err(unexpected(peek, "`fn` or `defextern`").with_note(
+                    "only functions and extern declarations are allowed at the top level",
+                ))
+            }
         };
 
         if let Some(expression) = expression {
             self.module.expressions.push(expression)
         }
+
+        Ok(())
     }
 
+    /// Parses every top level item in the file. Syntax errors are collected
+    /// in `diagnostics`, after which the parser resynchronises so that one run
+    /// reports as many errors as possible. Items that could not be parsed are
+    /// left out of the module.
     pub fn parse_module(&mut self) {
-        while self.tokens.peek().is_some() {
-            self.parse_top_level_expression()
+        while self.peek().is_some() {
+            let remaining = self.tokens.len();
+
+            if let Err(diagnostic) = self.parse_top_level_expression() {
+                self.diagnostics.push(diagnostic);
+
+                // always make progress, even when the error is on a token we
+                // would resynchronise at
+                if self.tokens.len() == remaining {
+                    self.next_token();
+                }
+
+                self.synchronise(0);
+            }
         }
     }
 }
+
+fn unexpected(token: &Token, expected: &str) -> Diagnostic {
+    Diagnostic::error(format!("expected {}, found {}", expected, token.value))
+        .with_primary(token.span, format!("expected {}", expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::tokenizer::Tokenizer;
+
+    /// The names of the top level items of the partial module parsed from
+    /// `source`, and the messages and primary spans of the syntax errors.
+    fn parse(source: &str) -> (Vec<String>, Vec<(String, Span)>) {
+        let mut tokenizer = Tokenizer::new(source);
+        tokenizer.parse();
+        assert!(
+            tokenizer.diagnostics.is_empty(),
+            "{:?}",
+            tokenizer.diagnostics
+        );
+
+        let mut parser = Parser::new(&tokenizer.tokens);
+        parser.parse_module();
+
+        let names = parser
+            .module
+            .expressions
+            .iter()
+            .map(|expression| match expression {
+                Expression::Function(expr) => expr.name.clone(),
+                Expression::ExternFunction(expr) => expr.name.clone(),
+                expr => panic!("not a top level item: {:?}", expr),
+            })
+            .collect();
+        let errors = parser
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                let span = diagnostic.primary_span().unwrap();
+                (diagnostic.message, span)
+            })
+            .collect();
+        (names, errors)
+    }
+
+    #[test]
+    fn every_syntax_error_is_reported() {
+        let source = "fn one do 1 end\n\
+                      fn two do 1 + end\n\
+                      fn three(x) do x end\n\
+                      defextern four(\n\
+                      fn five do 5 end\n";
+        let (names, errors) = parse(source);
+
+        assert_eq!(names, ["one", "two", "three", "five"]);
+        assert_eq!(
+            errors,
+            [
+                (
+                    "expected an expression, found keyword `end`".to_string(),
+                    Span::new(30, 33, 2, 15)
+                ),
+                (
+                    "expected an identifier, found keyword `fn`".to_string(),
+                    Span::new(71, 73, 5, 1)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn parsing_resumes_after_an_error_in_a_block() {
+        let source = "fn one do\n\
+                      \x20   if true do ) end\n\
+                      \x20   2\n\
+                      end\n\
+                      fn two do 2 end\n";
+        let (names, errors) = parse(source);
+
+        assert_eq!(names, ["one", "two"]);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].1.line, 2);
+    }
+}
@@ -1,59 +1,189 @@
+use super::types::NAMED_TYPES;
+use super::{Span, Type};
+use crate::diagnostics::Diagnostic;
 use std::convert::TryFrom;
+use std::fmt;
 use std::iter::Peekable;
 use std::str::Chars;
+use unicode_xid::UnicodeXID;
 
 pub struct Tokenizer<'a> {
     buffer: Peekable<Chars<'a>>,
+    offset: usize,
+    line: usize,
+    column: usize,
     pub tokens: Vec<Token>,
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 #[derive(Clone, Debug)]
 pub struct Token {
     pub value: TokenValue,
+    pub span: Span,
 }
 
 impl Token {
-    pub fn new(value: TokenValue) -> Token {
-        Token { value }
-    }
-
-    pub fn keyword(kw: Keyword) -> Token {
-        Token::new(TokenValue::Keyword(kw))
-    }
-
-    pub fn identifier(string: String) -> Token {
-        Token::new(TokenValue::Identifier(string))
-    }
-
-    pub fn num_const(i: f64) -> Token {
-        Token::new(TokenValue::NumConst(i))
+    pub fn new(value: TokenValue, span: Span) -> Token {
+        Token { value, span }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum TokenValue {
     Keyword(Keyword),
     Identifier(String),
-    NumConst(f64),
+    /// An integer literal and its type suffix, `Type::Unknown` without one.
+    IntConst(u64, Type),
+    FloatConst(f64, Type),
+    /// A string literal with its escape sequences already replaced.
+    StringConst(String),
     Operator(BinaryOperator),
+    UnaryOperator(UnaryOperator),
     OpenParen,
     CloseParen,
     Comma,
+    Colon,
+    Arrow,
+    Assign,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Keyword {
     Fn,
     Do,
     End,
     DefExtern,
+    True,
+    False,
+    If,
+    Else,
+    While,
+    Break,
+    Continue,
+    Return,
+    Let,
+    Var,
+    As,
 }
 
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq)]
 pub enum BinaryOperator {
     Add,
     Sub,
     Mul,
+    Div,
+    Rem,
+    Lt,
+    Gt,
+    Le,
+    Ge,
+    Eq,
+    Ne,
+    BitAnd,
+    BitOr,
+    BitXor,
+    Shl,
+    Shr,
+    And,
+    Or,
+}
+
+/// Prefix operators. Negation is written with the `-` of `BinaryOperator::Sub`,
+/// the parser tells them apart by position.
+#[derive(Clone, Debug, Copy, PartialEq)]
+pub enum UnaryOperator {
+    Neg,
+    Not,
+    BitNot,
+}
+
+impl fmt::Display for TokenValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenValue::Keyword(keyword) => write!(f, "keyword `{}`", keyword),
+            TokenValue::Identifier(identifier) => write!(f, "identifier `{}`", identifier),
+            TokenValue::IntConst(number, _) => write!(f, "number `{}`", number),
+            TokenValue::FloatConst(number, _) => write!(f, "number `{:?}`", number),
+            TokenValue::StringConst(string) => write!(f, "string {:?}", string),
+            TokenValue::Operator(operator) => write!(f, "operator `{}`", operator),
+            TokenValue::UnaryOperator(operator) => write!(f, "operator `{}`", operator),
+            TokenValue::OpenParen => write!(f, "`(`"),
+            TokenValue::CloseParen => write!(f, "`)`"),
+            TokenValue::Comma => write!(f, "`,`"),
+            TokenValue::Colon => write!(f, "`:`"),
+            TokenValue::Arrow => write!(f, "`->`"),
+            TokenValue::Assign => write!(f, "`=`"),
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Keyword::*;
+
+        let keyword = match self {
+            Fn => "fn",
+            Do => "do",
+            End => "end",
+            DefExtern => "defextern",
+            True => "true",
+            False => "false",
+            If => "if",
+            Else => "else",
+            While => "while",
+            Break => "break",
+            Continue => "continue",
+            Return => "return",
+            Let => "let",
+            Var => "var",
+            As => "as",
+        };
+
+        write!(f, "{}", keyword)
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use UnaryOperator::*;
+
+        let operator = match self {
+            Neg => "-",
+            Not => "!",
+            BitNot => "~",
+        };
+
+        write!(f, "{}", operator)
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use BinaryOperator::*;
+
+        let operator = match self {
+            Add => "+",
+            Sub => "-",
+            Mul => "*",
+            Div => "/",
+            Rem => "%",
+            Lt => "<",
+            Gt => ">",
+            Le => "<=",
+            Ge => ">=",
+            Eq => "==",
+            Ne => "!=",
+            BitAnd => "&",
+            BitOr => "|",
+            BitXor => "^",
+            Shl => "<<",
+            Shr => ">>",
+            And => "&&",
+            Or => "||",
+        };
+
+        write!(f, "{}", operator)
+    }
 }
 
 impl TryFrom<&str> for Keyword {
@@ -67,6 +197,17 @@ impl TryFrom<&str> for Keyword {
             "do" => Ok(Do),
             "end" => Ok(End),
             "defextern" => Ok(DefExtern),
+            "true" => Ok(True),
+            "false" => Ok(False),
+            "if" => Ok(If),
+            "else" => Ok(Else),
+            "while" => Ok(While),
+            "break" => Ok(Break),
+            "continue" => Ok(Continue),
+            "return" => Ok(Return),
+            "let" => Ok(Let),
+            "var" => Ok(Var),
+            "as" => Ok(As),
             _ => Err("Invalid keyword"),
         }
     }
@@ -80,7 +221,47 @@ impl TryFrom<String> for Keyword {
     }
 }
 
+/// Identifiers follow Unicode Standard Annex #31: they start with a character
+/// with the `XID_Start` property or an underscore, followed by any number of
+/// `XID_Continue` characters, which include digits. Keywords are reserved,
+/// but can be used as a name by writing them as `r#end`.
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || UnicodeXID::is_xid_start(c)
+}
+
+fn is_identifier_continue(c: char) -> bool {
+    UnicodeXID::is_xid_continue(c)
+}
+
 impl BinaryOperator {
+    /// Comparisons produce a `bool` instead of a value of their operand type.
+    pub fn is_comparison(&self) -> bool {
+        use BinaryOperator::*;
+
+        matches!(self, Lt | Gt | Le | Ge | Eq | Ne)
+    }
+
+    /// `&`, `|` and `^`, which work on integers and on `bool`.
+    pub fn is_bitwise(&self) -> bool {
+        use BinaryOperator::*;
+
+        matches!(self, BitAnd | BitOr | BitXor)
+    }
+
+    /// `&&` and `||`, which only evaluate their right side when the left
+    /// side does not decide the result.
+    pub fn is_logical(&self) -> bool {
+        use BinaryOperator::*;
+
+        matches!(self, And | Or)
+    }
+
+    pub fn is_shift(&self) -> bool {
+        use BinaryOperator::*;
+
+        matches!(self, Shl | Shr)
+    }
+
     pub fn precedence(&self) -> u16 {
         use BinaryOperator::*;
 
@@ -88,23 +269,38 @@ impl BinaryOperator {
         // 	'=': 1,
         // 	'||': 2,
         // 	'&&': 3,
-        // 	'<': 7, '>': 7, '<=': 7, '>=': 7, '==': 7, '!=': 7,
+        // 	'<': 4, '>': 4, '<=': 4, '>=': 4, '==': 4, '!=': 4,
+        // 	'|': 5,
+        // 	'^': 6,
+        // 	'&': 7,
+        // 	'<<': 8, '>>': 8,
         // 	'+': 10, '-': 10,
         // 	'*': 20, '/': 20, '%': 20,
         // };
 
         match self {
+            Or => 2,
+            And => 3,
+            Lt | Gt | Le | Ge | Eq | Ne => 4,
+            BitOr => 5,
+            BitXor => 6,
+            BitAnd => 7,
+            Shl | Shr => 8,
             Add | Sub => 10,
-            Mul => 20,
+            Mul | Div | Rem => 20,
         }
     }
 }
 
 impl Tokenizer<'_> {
-    pub fn new(buffer: &'_ String) -> Tokenizer<'_> {
+    pub fn new(buffer: &'_ str) -> Tokenizer<'_> {
         Tokenizer {
             buffer: buffer.chars().peekable(),
+            offset: 0,
+            line: 1,
+            column: 1,
             tokens: vec![],
+            diagnostics: vec![],
         }
     }
 
@@ -114,18 +310,52 @@ impl Tokenizer<'_> {
         }
     }
 
+    /// Consumes the next character, keeping track of where we are in the source.
+    fn bump(&mut self) -> Option<char> {
+        let c = self.buffer.next()?;
+
+        self.offset += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+
+        Some(c)
+    }
+
+    /// Consumes the next character if it is `next`, for two character operators.
+    fn bump_if(&mut self, next: char) -> bool {
+        if self.buffer.peek() == Some(&next) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the buffer continues with `#` and the start of an identifier,
+    /// which after an `r` makes a raw identifier rather than a comment.
+    fn at_raw_identifier(&self) -> bool {
+        let mut lookahead = self.buffer.clone();
+        lookahead.next() == Some('#') && lookahead.next().is_some_and(is_identifier_start)
+    }
+
+    /// Returns a span from the given start position up to the current position.
+    fn span_from(&self, start: usize, line: usize, column: usize) -> Span {
+        Span::new(start, self.offset, line, column)
+    }
+
     pub fn read_while<F>(&mut self, f: F) -> String
     where
         F: Fn(&char) -> bool,
     {
         let mut accumulator: Vec<char> = Vec::new();
-        while self.buffer.by_ref().peek().is_some() {
-            if let Some(peek) = self.buffer.by_ref().peek() {
-                if f(peek) {
-                    accumulator.push(self.buffer.next().unwrap());
-                } else {
-                    break;
-                }
+        while let Some(peek) = self.buffer.peek() {
+            if f(peek) {
+                let c = self.bump().unwrap();
+                accumulator.push(c);
             } else {
                 break;
             }
@@ -134,71 +364,621 @@ impl Tokenizer<'_> {
         accumulator.into_iter().collect()
     }
 
-    fn parse_token(&mut self) {
-        let peek: &char = { self.buffer.peek().clone().unwrap() };
+    /// Whether `f` holds for the character after the next one.
+    fn second_char_is<F>(&self, f: F) -> bool
+    where
+        F: Fn(char) -> bool,
+    {
+        let mut lookahead = self.buffer.clone();
+        lookahead.next();
+        lookahead.next().is_some_and(f)
+    }
+
+    /// Reads a numeric literal:
+    ///
+    /// ```text
+    /// number   = (decimal | "0x" hex | "0o" octal | "0b" binary) suffix?
+    /// decimal  = digits ("." digits)? (("e" | "E") ("+" | "-")? digits)?
+    /// suffix   = a numeric type, like `i64` or `f64`
+    /// ```
+    ///
+    /// Digits can be separated by `_`. Only decimal literals can have a
+    /// fraction or an exponent, which makes them floats. Whether the value
+    /// fits in its type is checked by sema, once the type is known.
+    fn parse_number(&mut self, start: usize, line: usize, column: usize) -> Option<TokenValue> {
+        let radix = match self.buffer.peek().copied() {
+            Some('0') if self.second_char_is(|c| c == 'x') => 16,
+            Some('0') if self.second_char_is(|c| c == 'o') => 8,
+            Some('0') if self.second_char_is(|c| c == 'b') => 2,
+            _ => 10,
+        };
+        let prefix = match radix {
+            16 => "0x",
+            8 => "0o",
+            2 => "0b",
+            _ => "",
+        };
+        if radix != 10 {
+            self.bump();
+            self.bump();
+        }
+
+        let mut text = self.read_while(|c| c.is_digit(radix) || *c == '_');
+        let mut is_float = false;
+
+        if radix == 10 {
+            if self.buffer.peek() == Some(&'.') && self.second_char_is(|c| c.is_ascii_digit()) {
+                self.bump();
+                text.push('.');
+                text += &self.read_while(|c| c.is_ascii_digit() || *c == '_');
+                is_float = true;
+            }
+
+            if let Some('e') | Some('E') = self.buffer.peek() {
+                self.bump();
+                text.push('e');
+                if let Some(sign @ ('+' | '-')) = self.buffer.peek().copied() {
+                    self.bump();
+                    text.push(sign);
+                }
+
+                let exponent = self.read_while(|c| c.is_ascii_digit() || *c == '_');
+                if !exponent.chars().any(|c| c.is_ascii_digit()) {
+                    let span = self.span_from(start, line, column);
+                    self.diagnostics.push(
+                        Diagnostic::error("expected at least one digit in exponent")
+                            .with_primary(span, "exponent has no digits"),
+                    );
+                    return None;
+                }
+
+                text += &exponent;
+                is_float = true;
+            }
+        }
+
+        if self.buffer.peek() == Some(&'.') && self.second_char_is(|c| c.is_ascii_digit()) {
+            let rest = self.read_while(|c| c.is_ascii_digit() || *c == '.' || *c == '_');
+            let span = self.span_from(start, line, column);
+            let label = if is_float {
+                "a number can have only one decimal point"
+            } else {
+                "only decimal numbers can have a fraction"
+            };
+
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "invalid numeric literal `{}{}{}`",
+                    prefix, text, rest
+                ))
+                .with_primary(span, label),
+            );
+            return None;
+        }
+
+        let (suffix_start, suffix_line, suffix_column) = (self.offset, self.line, self.column);
+        let suffix = self.read_while(|c| is_identifier_continue(*c));
+        let suffix_span = self.span_from(suffix_start, suffix_line, suffix_column);
+        let span = self.span_from(start, line, column);
+
+        let digits: String = text.chars().filter(|c| *c != '_').collect();
+        if digits.is_empty() {
+            self.diagnostics.push(
+                Diagnostic::error("no valid digits found for number")
+                    .with_primary(span, "expected digits"),
+            );
+            return None;
+        }
+
+        let kind = if is_float {
+            "float"
+        } else {
+            match radix {
+                16 => "hexadecimal",
+                8 => "octal",
+                2 => "binary",
+                _ => "number",
+            }
+        };
+
+        let ty = if suffix.is_empty() {
+            Type::Unknown
+        } else {
+            let allowed: Vec<Type> = NAMED_TYPES
+                .iter()
+                .copied()
+                .filter(|ty| (ty.is_integer() && !is_float) || (ty.is_float() && radix == 10))
+                .collect();
+
+            match Type::from_name(&suffix) {
+                Some(ty) if allowed.contains(&ty) => ty,
+                _ => {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!(
+                            "invalid suffix `{}` for {} literal",
+                            suffix, kind
+                        ))
+                        .with_primary(suffix_span, format!("invalid suffix `{}`", suffix))
+                        .with_note(format!(
+                            "the suffix must be one of {}",
+                            allowed
+                                .iter()
+                                .map(|ty| format!("`{}`", ty))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )),
+                    );
+                    return None;
+                }
+            }
+        };
 
-        let token: Option<Token> = match peek {
-            // literals
-            peek if peek.is_numeric() => {
-                let number_str: String = self.read_while(|c| c.is_numeric() || *c == '.');
+        if is_float || ty.is_float() {
+            // out of range values parse as infinity, which sema reports
+            return digits
+                .parse()
+                .ok()
+                .map(|float| TokenValue::FloatConst(float, ty));
+        }
 
-                let number: f64 = number_str.parse().expect(
-                    format!("Numeric constant {:?} could not be parsed", number_str).as_str(),
+        match u64::from_str_radix(&digits, radix) {
+            Ok(integer) => Some(TokenValue::IntConst(integer, ty)),
+            Err(_) => {
+                self.diagnostics.push(
+                    Diagnostic::error("integer literal is too large")
+                        .with_primary(span, "does not fit in 64 bits")
+                        .with_note(format!("value exceeds limit of `{}`", u64::MAX)),
                 );
+                None
+            }
+        }
+    }
+
+    /// Reads a string literal, which can span multiple lines. The supported
+    /// escape sequences are `\n`, `\r`, `\t`, `\0`, `\\`, `\"`, `\'`, `\xHH` for
+    /// ASCII characters and `\u{HHHH}` for any Unicode character.
+    fn parse_string(&mut self, start: usize, line: usize, column: usize) -> Option<TokenValue> {
+        self.bump();
+
+        let mut string = String::new();
+        let mut valid = true;
+
+        loop {
+            let (escape_start, escape_line, escape_column) = (self.offset, self.line, self.column);
+
+            match self.bump() {
+                Some('"') => break,
+                Some('\\') => match self.parse_escape() {
+                    Ok(c) => string.push(c),
+                    Err((message, label)) => {
+                        let span = self.span_from(escape_start, escape_line, escape_column);
+                        self.diagnostics
+                            .push(Diagnostic::error(message).with_primary(span, label));
+                        valid = false;
+                    }
+                },
+                Some(c) => string.push(c),
+                None => {
+                    let span = Span::new(start, start + 1, line, column);
+                    self.diagnostics.push(
+                        Diagnostic::error("unterminated double quote string")
+                            .with_primary(span, "string starts here and is never closed"),
+                    );
+                    return None;
+                }
+            }
+        }
 
-                Some(Token::num_const(number))
+        if valid {
+            Some(TokenValue::StringConst(string))
+        } else {
+            None
+        }
+    }
+
+    /// Reads the rest of an escape sequence after the `\\`, returning the
+    /// character it stands for or the message and label of a diagnostic.
+    fn parse_escape(&mut self) -> Result<char, (String, &'static str)> {
+        let c = match self.bump() {
+            Some(c) => c,
+            None => {
+                return Err((
+                    "unterminated escape sequence".to_string(),
+                    "expected a character",
+                ))
             }
-            // identifiers
-            peek if peek.is_alphabetic() || *peek == '_' => {
-                let string: String = self.read_while(|c| c.is_alphabetic() || *c == '_');
+        };
 
-                let kw_string = string.clone();
-                if let Ok(kw) = Keyword::try_from(kw_string) {
-                    Some(Token::keyword(kw))
+        match c {
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            '0' => Ok('\0'),
+            '\\' | '"' | '\'' => Ok(c),
+            'x' => {
+                let mut digits = String::new();
+                while digits.len() < 2 {
+                    match self.buffer.peek() {
+                        Some(c) if c.is_ascii_hexdigit() => digits.push(*c),
+                        _ => break,
+                    }
+                    self.bump();
+                }
+
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(value) if digits.len() == 2 && value <= 0x7f => Ok(value as char),
+                    Ok(_) if digits.len() == 2 => Err((
+                        "out of range hex escape".to_string(),
+                        "must be a character in the range [\\x00-\\x7f]",
+                    )),
+                    _ => Err((
+                        "numeric character escape is too short".to_string(),
+                        "expected two hexadecimal digits",
+                    )),
+                }
+            }
+            'u' => {
+                if !self.bump_if('{') {
+                    return Err((
+                        "incorrect unicode escape sequence".to_string(),
+                        "expected `{` after `\\u`",
+                    ));
+                }
+
+                let digits = self.read_while(|c| c.is_ascii_hexdigit());
+                if !self.bump_if('}') || digits.is_empty() || digits.len() > 6 {
+                    return Err((
+                        "incorrect unicode escape sequence".to_string(),
+                        "expected one to six hexadecimal digits between `{` and `}`",
+                    ));
+                }
+
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        (
+                            "invalid unicode character escape".to_string(),
+                            "not a valid Unicode scalar value",
+                        )
+                    })
+            }
+            c => Err((
+                format!("unknown character escape: `{}`", c.escape_default()),
+                "unknown character escape",
+            )),
+        }
+    }
+
+    fn parse_token(&mut self) {
+        let peek: char = *self.buffer.peek().unwrap();
+        let (start, line, column) = (self.offset, self.line, self.column);
+
+        let value: Option<TokenValue> = match peek {
+            // literals, a malformed one is reported here and still becomes a
+            // literal so that the parser does not report it a second time
+            peek if peek.is_ascii_digit() => self
+                .parse_number(start, line, column)
+                .or(Some(TokenValue::IntConst(0, Type::Unknown))),
+            // identifiers, `r#` escapes a keyword so it can be used as a name
+            peek if is_identifier_start(peek) => {
+                let string: String = self.read_while(|c| is_identifier_continue(*c));
+
+                if string == "r" && self.at_raw_identifier() {
+                    self.bump();
+                    let name: String = self.read_while(|c| is_identifier_continue(*c));
+                    Some(TokenValue::Identifier(name))
+                } else if let Ok(kw) = Keyword::try_from(string.as_str()) {
+                    Some(TokenValue::Keyword(kw))
                 } else {
-                    Some(Token::identifier(string))
+                    Some(TokenValue::Identifier(string))
                 }
             }
             // comments
             '#' => {
-                self.buffer.by_ref().next();
+                self.bump();
                 self.read_while(|c| *c != '\n');
                 None
             }
             // operators
             '+' => {
-                self.buffer.by_ref().next();
-                Some(Token::new(TokenValue::Operator(BinaryOperator::Add)))
+                self.bump();
+                Some(TokenValue::Operator(BinaryOperator::Add))
             }
             '-' => {
-                self.buffer.by_ref().next();
-                Some(Token::new(TokenValue::Operator(BinaryOperator::Sub)))
+                self.bump();
+
+                if self.bump_if('>') {
+                    Some(TokenValue::Arrow)
+                } else {
+                    Some(TokenValue::Operator(BinaryOperator::Sub))
+                }
             }
             '*' => {
-                self.buffer.by_ref().next();
-                Some(Token::new(TokenValue::Operator(BinaryOperator::Mul)))
+                self.bump();
+                Some(TokenValue::Operator(BinaryOperator::Mul))
+            }
+            '/' => {
+                self.bump();
+                Some(TokenValue::Operator(BinaryOperator::Div))
             }
+            '%' => {
+                self.bump();
+                Some(TokenValue::Operator(BinaryOperator::Rem))
+            }
+            '<' => {
+                self.bump();
+
+                if self.bump_if('=') {
+                    Some(TokenValue::Operator(BinaryOperator::Le))
+                } else if self.bump_if('<') {
+                    Some(TokenValue::Operator(BinaryOperator::Shl))
+                } else {
+                    Some(TokenValue::Operator(BinaryOperator::Lt))
+                }
+            }
+            '>' => {
+                self.bump();
+
+                if self.bump_if('=') {
+                    Some(TokenValue::Operator(BinaryOperator::Ge))
+                } else if self.bump_if('>') {
+                    Some(TokenValue::Operator(BinaryOperator::Shr))
+                } else {
+                    Some(TokenValue::Operator(BinaryOperator::Gt))
+                }
+            }
+            '=' => {
+                self.bump();
+
+                if self.bump_if('=') {
+                    Some(TokenValue::Operator(BinaryOperator::Eq))
+                } else {
+                    Some(TokenValue::Assign)
+                }
+            }
+            '!' => {
+                self.bump();
+
+                if self.bump_if('=') {
+                    Some(TokenValue::Operator(BinaryOperator::Ne))
+                } else {
+                    Some(TokenValue::UnaryOperator(UnaryOperator::Not))
+                }
+            }
+            '~' => {
+                self.bump();
+                Some(TokenValue::UnaryOperator(UnaryOperator::BitNot))
+            }
+            '&' => {
+                self.bump();
+
+                if self.bump_if('&') {
+                    Some(TokenValue::Operator(BinaryOperator::And))
+                } else {
+                    Some(TokenValue::Operator(BinaryOperator::BitAnd))
+                }
+            }
+            '|' => {
+                self.bump();
+
+                if self.bump_if('|') {
+                    Some(TokenValue::Operator(BinaryOperator::Or))
+                } else {
+                    Some(TokenValue::Operator(BinaryOperator::BitOr))
+                }
+            }
+            '^' => {
+                self.bump();
+                Some(TokenValue::Operator(BinaryOperator::BitXor))
+            }
+            '"' => self
+                .parse_string(start, line, column)
+                .or_else(|| Some(TokenValue::StringConst(String::new()))),
             '(' => {
-                self.buffer.by_ref().next();
-                Some(Token::new(TokenValue::OpenParen))
+                self.bump();
+                Some(TokenValue::OpenParen)
             }
             ')' => {
-                self.buffer.by_ref().next();
-                Some(Token::new(TokenValue::CloseParen))
+                self.bump();
+                Some(TokenValue::CloseParen)
             }
             ',' => {
-                self.buffer.by_ref().next();
-                Some(Token::new(TokenValue::Comma))
+                self.bump();
+                Some(TokenValue::Comma)
+            }
+            ':' => {
+                self.bump();
+                Some(TokenValue::Colon)
             }
             peek if peek.is_whitespace() => {
-                self.buffer.by_ref().next();
+                self.bump();
+                None
+            }
+            // skipped after reporting it, the parser continues as if it
+            // was not there
+            _ => {
+                self.bump();
+                let span = self.span_from(start, line, column);
+                self.diagnostics.push(
+                    Diagnostic::error(format!("unknown character {:?}", peek))
+                        .with_primary(span, "not recognised by the tokenizer"),
+                );
                 None
             }
-            _ => panic!("Unknown character {:?}", peek),
         };
 
-        if let Some(token) = token {
-            self.tokens.push(token)
+        if let Some(value) = value {
+            let span = self.span_from(start, line, column);
+            self.tokens.push(Token::new(value, span))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The values of the tokens in `source` and the messages of the
+    /// diagnostics reported while reading them.
+    fn tokenize(source: &str) -> (Vec<TokenValue>, Vec<String>) {
+        let mut tokenizer = Tokenizer::new(source);
+        tokenizer.parse();
+
+        let values = tokenizer
+            .tokens
+            .into_iter()
+            .map(|token| token.value)
+            .collect();
+        let messages = tokenizer
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect();
+        (values, messages)
+    }
+
+    fn single_token(source: &str) -> TokenValue {
+        let (mut values, messages) = tokenize(source);
+        assert!(messages.is_empty(), "{}: {:?}", source, messages);
+        assert_eq!(values.len(), 1, "{}: {:?}", source, values);
+        values.remove(0)
+    }
+
+    fn single_error(source: &str) -> String {
+        let (_, mut messages) = tokenize(source);
+        assert_eq!(messages.len(), 1, "{}: {:?}", source, messages);
+        messages.remove(0)
+    }
+
+    #[test]
+    fn integer_literals() {
+        let cases = [
+            ("0", 0),
+            ("1_000_000", 1_000_000),
+            ("0xff", 255),
+            ("0xFF_FF", 0xffff),
+            ("0o17", 15),
+            ("0b1010", 10),
+            ("18446744073709551615", u64::MAX),
+        ];
+
+        for (source, value) in cases {
+            assert_eq!(
+                single_token(source),
+                TokenValue::IntConst(value, Type::Unknown),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn float_literals() {
+        let cases = [
+            ("1.5", 1.5),
+            ("1e9", 1e9),
+            ("2.5e-3", 2.5e-3),
+            ("1E+2", 100.0),
+            ("1_0.2_5", 10.25),
+        ];
+
+        for (source, value) in cases {
+            assert_eq!(
+                single_token(source),
+                TokenValue::FloatConst(value, Type::Unknown),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn suffixed_literals() {
+        assert_eq!(single_token("10u8"), TokenValue::IntConst(10, Type::U8));
+        assert_eq!(
+            single_token("0xffi32"),
+            TokenValue::IntConst(0xff, Type::I32)
+        );
+        assert_eq!(single_token("7usize"), TokenValue::IntConst(7, Type::Usize));
+        assert_eq!(
+            single_token("3.0f32"),
+            TokenValue::FloatConst(3.0, Type::F32)
+        );
+        assert_eq!(single_token("7f64"), TokenValue::FloatConst(7.0, Type::F64));
+    }
+
+    #[test]
+    fn malformed_literals() {
+        let cases = [
+            ("1.2.3", "invalid numeric literal `1.2.3`"),
+            ("0x1.5", "invalid numeric literal `0x1.5`"),
+            ("1e", "expected at least one digit in exponent"),
+            ("1e+_", "expected at least one digit in exponent"),
+            ("0x", "no valid digits found for number"),
+            ("0b_", "no valid digits found for number"),
+            ("1.5u8", "invalid suffix `u8` for float literal"),
+            ("0o7f32", "invalid suffix `f32` for octal literal"),
+            ("0b12", "invalid suffix `2` for binary literal"),
+            ("10i128", "invalid suffix `i128` for number literal"),
+            ("18446744073709551616", "integer literal is too large"),
+        ];
+
+        for (source, message) in cases {
+            assert_eq!(single_error(source), message, "{}", source);
+        }
+    }
+
+    #[test]
+    fn malformed_literal_is_still_a_single_token() {
+        let (values, messages) = tokenize("1.2.3 + 4");
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            values,
+            vec![
+                TokenValue::IntConst(0, Type::Unknown),
+                TokenValue::Operator(BinaryOperator::Add),
+                TokenValue::IntConst(4, Type::Unknown),
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        let cases = [
+            (r#""a\nb""#, "a\nb"),
+            (r#""\r\t\0""#, "\r\t\0"),
+            (r#""\\ \" \'""#, "\\ \" '"),
+            (r#""\x41\x7f""#, "A\x7f"),
+            (r#""\u{48}\u{1F600}""#, "H\u{1F600}"),
+        ];
+
+        for (source, value) in cases {
+            assert_eq!(
+                single_token(source),
+                TokenValue::StringConst(value.to_string()),
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_escapes() {
+        let cases = [
+            (r#""\q""#, "unknown character escape: `q`"),
+            (r#""\x80""#, "out of range hex escape"),
+            (r#""\x4""#, "numeric character escape is too short"),
+            (r#""\u48""#, "incorrect unicode escape sequence"),
+            (r#""\u{}""#, "incorrect unicode escape sequence"),
+            (r#""\u{1234567}""#, "incorrect unicode escape sequence"),
+            (r#""\u{D800}""#, "invalid unicode character escape"),
+            (r#""abc"#, "unterminated double quote string"),
+        ];
+
+        for (source, message) in cases {
+            assert_eq!(single_error(source), message, "{}", source);
         }
     }
 }
@@ -0,0 +1,139 @@
+use std::fmt;
+
+/// The type of a value. `Unknown` is what the parser assigns to anything that
+/// has not been annotated, sema replaces it before codegen runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Type {
+    Unknown,
+    /// The type of expressions that never produce a value, like `break`.
+    Never,
+    /// The return type of functions that do not produce a value, like a
+    /// `void` function in C.
+    Void,
+    Bool,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    /// An unsigned integer as wide as a pointer, like `size_t` in C. Only
+    /// 64-bit targets are supported.
+    Usize,
+    F32,
+    F64,
+    /// An untyped pointer, like `char *` or `void *` in C. String literals
+    /// are pointers to their first character.
+    Ptr,
+    /// A type that is still being inferred, this only exists while sema
+    /// runs. See `sema::infer`.
+    Variable(u32),
+}
+
+/// Every type that can be written in an annotation.
+pub const NAMED_TYPES: &[Type] = &[
+    Type::Void,
+    Type::Bool,
+    Type::I8,
+    Type::I16,
+    Type::I32,
+    Type::I64,
+    Type::U8,
+    Type::U16,
+    Type::U32,
+    Type::U64,
+    Type::Usize,
+    Type::F32,
+    Type::F64,
+    Type::Ptr,
+];
+
+impl Type {
+    /// Looks up a type by the name used for it in annotations.
+    pub fn from_name(name: &str) -> Option<Type> {
+        NAMED_TYPES
+            .iter()
+            .find(|ty| ty.to_string() == name)
+            .copied()
+    }
+
+    pub fn is_known(&self) -> bool {
+        *self != Type::Unknown
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.integer_width().is_some()
+    }
+
+    pub fn is_signed_integer(&self) -> bool {
+        matches!(self, Type::I8 | Type::I16 | Type::I32 | Type::I64)
+    }
+
+    /// The number of bits in an integer type.
+    pub fn integer_width(&self) -> Option<u32> {
+        match self {
+            Type::I8 | Type::U8 => Some(8),
+            Type::I16 | Type::U16 => Some(16),
+            Type::I32 | Type::U32 => Some(32),
+            Type::I64 | Type::U64 | Type::Usize => Some(64),
+            _ => None,
+        }
+    }
+
+    /// The smallest and largest value of an integer type.
+    pub fn integer_range(&self) -> Option<(i128, i128)> {
+        let width = self.integer_width()?;
+
+        if self.is_signed_integer() {
+            Some((-(1 << (width - 1)), (1 << (width - 1)) - 1))
+        } else {
+            Some((0, (1 << width) - 1))
+        }
+    }
+
+    /// The largest finite value of a float type.
+    pub fn float_max(&self) -> Option<f64> {
+        match self {
+            Type::F32 => Some(f32::MAX as f64),
+            Type::F64 => Some(f64::MAX),
+            _ => None,
+        }
+    }
+
+    /// Whether arithmetic is defined on values of this type.
+    pub fn is_numeric(&self) -> bool {
+        self.is_integer() || self.is_float()
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Unknown => "{unknown}",
+            Type::Never => "!",
+            Type::Void => "void",
+            Type::Bool => "bool",
+            Type::I8 => "i8",
+            Type::I16 => "i16",
+            Type::I32 => "i32",
+            Type::I64 => "i64",
+            Type::U8 => "u8",
+            Type::U16 => "u16",
+            Type::U32 => "u32",
+            Type::U64 => "u64",
+            Type::Usize => "usize",
+            Type::F32 => "f32",
+            Type::F64 => "f64",
+            Type::Ptr => "ptr",
+            Type::Variable(_) => "_",
+        };
+
+        write!(f, "{}", name)
+    }
+}
@@ -0,0 +1,48 @@
+use super::infer::{InferenceTable, Kind};
+use crate::parser::Type;
+
+/// Functions provided by the compiler itself, codegen lowers calls to these
+/// directly to instructions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Builtin {
+    ToFloat,
+    ToInt,
+    /// Integer arithmetic that wraps around on overflow, also in debug
+    /// builds.
+    WrappingAdd,
+    WrappingSub,
+    WrappingMul,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Builtin> {
+        match name {
+            "to_float" => Some(Builtin::ToFloat),
+            "to_int" => Some(Builtin::ToInt),
+            "wrapping_add" => Some(Builtin::WrappingAdd),
+            "wrapping_sub" => Some(Builtin::WrappingSub),
+            "wrapping_mul" => Some(Builtin::WrappingMul),
+            _ => None,
+        }
+    }
+
+    pub fn arity(&self) -> usize {
+        match self {
+            Builtin::ToFloat | Builtin::ToInt => 1,
+            Builtin::WrappingAdd | Builtin::WrappingSub | Builtin::WrappingMul => 2,
+        }
+    }
+
+    /// The parameter and return types of a call to the builtin. The wrapping
+    /// builtins work on any integer type, which is inferred per call.
+    pub fn signature(&self, table: &mut InferenceTable) -> (Vec<Type>, Type) {
+        match self {
+            Builtin::ToFloat => (vec![Type::I64], Type::F64),
+            Builtin::ToInt => (vec![Type::F64], Type::I64),
+            Builtin::WrappingAdd | Builtin::WrappingSub | Builtin::WrappingMul => {
+                let ty = table.new_variable(Kind::Integer);
+                (vec![ty, ty], ty)
+            }
+        }
+    }
+}
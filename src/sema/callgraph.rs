@@ -0,0 +1,162 @@
+use crate::parser::{BlockExpression, Expression, Module};
+
+use std::collections::HashMap;
+
+/// Groups the functions of a module that call each other, directly or through
+/// other functions, as indices into `module.expressions`. Every group comes
+/// after the groups it calls into, so checking them in order checks a callee
+/// before its callers unless they are in the same group.
+pub fn function_groups(module: &Module) -> Vec<Vec<usize>> {
+    let indices: HashMap<&str, usize> = module
+        .expressions
+        .iter()
+        .enumerate()
+        .filter_map(|(i, expression)| match expression {
+            Expression::Function(expr) => Some((expr.name.as_str(), i)),
+            _ => None,
+        })
+        .collect();
+
+    let mut graph = CallGraph {
+        callees: HashMap::new(),
+        visits: HashMap::new(),
+        stack: Vec::new(),
+        groups: Vec::new(),
+    };
+
+    for (i, expression) in module.expressions.iter().enumerate() {
+        if let Expression::Function(expr) = expression {
+            let mut names = Vec::new();
+            collect_block_calls(&expr.body, &mut names);
+
+            let callees = names
+                .into_iter()
+                .filter_map(|name| indices.get(name).copied())
+                .collect();
+            graph.callees.insert(i, callees);
+        }
+    }
+
+    let mut functions: Vec<usize> = graph.callees.keys().copied().collect();
+    functions.sort_unstable();
+    for function in functions {
+        if !graph.visits.contains_key(&function) {
+            graph.visit(function);
+        }
+    }
+
+    graph.groups
+}
+
+#[derive(Clone, Copy)]
+struct Visit {
+    index: usize,
+    lowest_link: usize,
+    on_stack: bool,
+}
+
+/// Tarjan's strongly connected components algorithm, which finds the groups
+/// in the order `function_groups` returns them.
+struct CallGraph {
+    callees: HashMap<usize, Vec<usize>>,
+    visits: HashMap<usize, Visit>,
+    stack: Vec<usize>,
+    groups: Vec<Vec<usize>>,
+}
+
+impl CallGraph {
+    fn visit(&mut self, function: usize) {
+        let index = self.visits.len();
+        self.visits.insert(
+            function,
+            Visit {
+                index,
+                lowest_link: index,
+                on_stack: true,
+            },
+        );
+        self.stack.push(function);
+
+        for callee in self.callees[&function].clone() {
+            let lowest_link = match self.visits.get(&callee) {
+                None => {
+                    self.visit(callee);
+                    self.visits[&callee].lowest_link
+                }
+                Some(visit) if visit.on_stack => visit.index,
+                Some(_) => continue,
+            };
+
+            let visit = self.visits.get_mut(&function).unwrap();
+            visit.lowest_link = visit.lowest_link.min(lowest_link);
+        }
+
+        let visit = self.visits[&function];
+        if visit.lowest_link != visit.index {
+            return;
+        }
+
+        let mut group = Vec::new();
+        loop {
+            let member = self.stack.pop().unwrap();
+            self.visits.get_mut(&member).unwrap().on_stack = false;
+            group.push(member);
+
+            if member == function {
+                break;
+            }
+        }
+
+        group.sort_unstable();
+        self.groups.push(group);
+    }
+}
+
+fn collect_block_calls<'a>(block: &'a BlockExpression, names: &mut Vec<&'a str>) {
+    for expression in &block.expressions {
+        collect_calls(expression, names);
+    }
+}
+
+/// The names of the functions called anywhere in an expression.
+fn collect_calls<'a>(expression: &'a Expression, names: &mut Vec<&'a str>) {
+    match expression {
+        Expression::Binary(expr) => {
+            collect_calls(&expr.left, names);
+            collect_calls(&expr.right, names);
+        }
+        Expression::Unary(expr) => collect_calls(&expr.operand, names),
+        Expression::Cast(expr) => collect_calls(&expr.value, names),
+        Expression::Call(expr) => {
+            names.push(&expr.function_name);
+            for arg in &expr.args {
+                collect_calls(arg, names);
+            }
+        }
+        Expression::If(expr) => {
+            collect_calls(&expr.condition, names);
+            collect_block_calls(&expr.then_block, names);
+            if let Some(else_block) = &expr.else_block {
+                collect_block_calls(else_block, names);
+            }
+        }
+        Expression::While(expr) => {
+            collect_calls(&expr.condition, names);
+            collect_block_calls(&expr.body, names);
+        }
+        Expression::Let(expr) => collect_calls(&expr.value, names),
+        Expression::Assign(expr) => collect_calls(&expr.value, names),
+        Expression::Return(expr) => {
+            if let Some(value) = &expr.value {
+                collect_calls(value, names);
+            }
+        }
+        Expression::NumberLiteral(_)
+        | Expression::BooleanLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::Variable(_)
+        | Expression::LoopControl(_)
+        | Expression::Function(_)
+        | Expression::ExternFunction(_) => {}
+    }
+}
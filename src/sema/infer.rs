@@ -0,0 +1,339 @@
+use crate::parser::Type;
+
+/// What an unsolved type variable may still turn out to be. Each kind allows
+/// fewer types than the one before it, an integer literal for example is an
+/// `Integer` until something decides between `i32` and `i64`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    /// The return type of a function, which may be `void`.
+    Any,
+    /// Anything that can be bound to a name or passed as an argument.
+    Value,
+    /// The operands of arithmetic.
+    Numeric,
+    Integer,
+    Float,
+}
+
+impl Kind {
+    fn admits(self, ty: Type) -> bool {
+        match self {
+            Kind::Any => true,
+            Kind::Value => ty != Type::Void,
+            Kind::Numeric => ty.is_numeric(),
+            Kind::Integer => ty.is_integer(),
+            Kind::Float => ty.is_float(),
+        }
+    }
+
+    fn rank(self) -> u8 {
+        match self {
+            Kind::Any => 0,
+            Kind::Value => 1,
+            Kind::Numeric => 2,
+            Kind::Integer | Kind::Float => 3,
+        }
+    }
+
+    /// The kind of a variable that has to satisfy both `self` and `other`,
+    /// `None` when no type can.
+    fn meet(self, other: Kind) -> Option<Kind> {
+        if self.rank() == 3 && other.rank() == 3 && self != other {
+            None
+        } else if self.rank() >= other.rank() {
+            Some(self)
+        } else {
+            Some(other)
+        }
+    }
+
+    /// The type a variable ends up with when nothing constrains it further.
+    fn default_type(self) -> Type {
+        match self {
+            Kind::Float => Type::F64,
+            _ => Type::I64,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Kind::Any => "`_`",
+            Kind::Value => "a value",
+            Kind::Numeric => "a number",
+            Kind::Integer => "an integer",
+            Kind::Float => "a float",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Slot {
+    Unbound(Kind),
+    Bound(Type),
+    /// Unified with another variable, which is the one that holds the
+    /// solution.
+    Link(u32),
+}
+
+/// The type variables of a module and what is known about them so far.
+/// `Type::Unknown` is the type of expressions that already had an error and
+/// unifies with everything, as does `!`, so that no mistake is reported
+/// twice.
+#[derive(Default)]
+pub struct InferenceTable {
+    slots: Vec<Slot>,
+}
+
+impl InferenceTable {
+    pub fn new() -> Self {
+        InferenceTable::default()
+    }
+
+    pub fn new_variable(&mut self, kind: Kind) -> Type {
+        self.slots.push(Slot::Unbound(kind));
+        Type::Variable(self.slots.len() as u32 - 1)
+    }
+
+    /// New variables of the same kinds as `variables`, which stand for the
+    /// same types at one use of a generic function.
+    pub fn instantiate(&mut self, variables: &[u32]) -> Vec<Type> {
+        variables
+            .iter()
+            .map(|variable| self.new_variable(self.kind(*variable)))
+            .collect()
+    }
+
+    fn root(&self, mut variable: u32) -> u32 {
+        while let Slot::Link(next) = self.slots[variable as usize] {
+            variable = next;
+        }
+
+        variable
+    }
+
+    /// Replaces a solved variable by its type. Unsolved variables are
+    /// replaced by the variable that represents everything unified with it.
+    pub fn resolve(&self, ty: Type) -> Type {
+        match ty {
+            Type::Variable(variable) => {
+                let root = self.root(variable);
+                match self.slots[root as usize] {
+                    Slot::Bound(ty) => ty,
+                    _ => Type::Variable(root),
+                }
+            }
+            _ => ty,
+        }
+    }
+
+    fn kind(&self, variable: u32) -> Kind {
+        match self.slots[self.root(variable) as usize] {
+            Slot::Unbound(kind) => kind,
+            _ => Kind::Any,
+        }
+    }
+
+    /// Makes two types equal, returns `false` when they cannot be.
+    pub fn unify(&mut self, a: Type, b: Type) -> bool {
+        let a = self.resolve(a);
+        let b = self.resolve(b);
+
+        match (a, b) {
+            _ if a == b => true,
+            (Type::Unknown, _) | (_, Type::Unknown) | (Type::Never, _) | (_, Type::Never) => true,
+            (Type::Variable(x), Type::Variable(y)) => match self.kind(x).meet(self.kind(y)) {
+                Some(kind) => {
+                    self.slots[x as usize] = Slot::Link(y);
+                    self.slots[y as usize] = Slot::Unbound(kind);
+                    true
+                }
+                None => false,
+            },
+            (Type::Variable(variable), ty) | (ty, Type::Variable(variable))
+                if self.kind(variable).admits(ty) =>
+            {
+                self.slots[variable as usize] = Slot::Bound(ty);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Restricts a type to a kind, returns `false` when it does not fit.
+    pub fn require(&mut self, ty: Type, kind: Kind) -> bool {
+        match self.resolve(ty) {
+            Type::Variable(variable) => match self.kind(variable).meet(kind) {
+                Some(kind) => {
+                    self.slots[variable as usize] = Slot::Unbound(kind);
+                    true
+                }
+                None => false,
+            },
+            Type::Unknown | Type::Never => true,
+            ty => kind.admits(ty),
+        }
+    }
+
+    /// Whether `require` would succeed, without restricting anything.
+    pub fn could_be(&self, ty: Type, kind: Kind) -> bool {
+        match self.resolve(ty) {
+            Type::Variable(variable) => self.kind(variable).meet(kind).is_some(),
+            Type::Unknown | Type::Never => true,
+            ty => kind.admits(ty),
+        }
+    }
+
+    /// The type that is left once inference is done, variables nothing
+    /// decided on get the default of their kind.
+    pub fn finish(&self, ty: Type) -> Type {
+        match self.resolve(ty) {
+            Type::Variable(variable) => self.kind(variable).default_type(),
+            ty => ty,
+        }
+    }
+
+    /// How to refer to a type in a diagnostic, such as "`i64`" or, when it
+    /// is only known to be some integer, "an integer".
+    pub fn describe(&self, ty: Type) -> String {
+        match self.resolve(ty) {
+            Type::Variable(variable) => self.kind(variable).describe().to_string(),
+            ty => format!("`{}`", ty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meet_keeps_the_narrower_kind() {
+        assert_eq!(Kind::Any.meet(Kind::Value), Some(Kind::Value));
+        assert_eq!(Kind::Numeric.meet(Kind::Value), Some(Kind::Numeric));
+        assert_eq!(Kind::Value.meet(Kind::Integer), Some(Kind::Integer));
+        assert_eq!(Kind::Float.meet(Kind::Numeric), Some(Kind::Float));
+        assert_eq!(Kind::Float.meet(Kind::Float), Some(Kind::Float));
+    }
+
+    #[test]
+    fn integers_and_floats_do_not_meet() {
+        assert_eq!(Kind::Integer.meet(Kind::Float), None);
+        assert_eq!(Kind::Float.meet(Kind::Integer), None);
+    }
+
+    #[test]
+    fn variable_unifies_with_a_type_of_its_kind() {
+        let mut table = InferenceTable::new();
+        let integer = table.new_variable(Kind::Integer);
+
+        assert!(!table.unify(integer, Type::F64));
+        assert!(!table.unify(integer, Type::Bool));
+        assert!(table.unify(integer, Type::U8));
+        assert_eq!(table.resolve(integer), Type::U8);
+
+        // once solved it is that type and nothing else
+        assert!(table.unify(integer, Type::U8));
+        assert!(!table.unify(integer, Type::I64));
+    }
+
+    #[test]
+    fn only_return_types_can_be_void() {
+        let mut table = InferenceTable::new();
+        let value = table.new_variable(Kind::Value);
+        let any = table.new_variable(Kind::Any);
+
+        assert!(!table.unify(value, Type::Void));
+        assert!(table.unify(any, Type::Void));
+        assert_eq!(table.resolve(any), Type::Void);
+    }
+
+    #[test]
+    fn unified_variables_share_their_solution() {
+        let mut table = InferenceTable::new();
+        let a = table.new_variable(Kind::Value);
+        let b = table.new_variable(Kind::Integer);
+
+        assert!(table.unify(a, b));
+        assert_eq!(table.describe(a), "an integer");
+
+        assert!(table.unify(b, Type::I16));
+        assert_eq!(table.resolve(a), Type::I16);
+    }
+
+    #[test]
+    fn integer_and_float_variables_do_not_unify() {
+        let mut table = InferenceTable::new();
+        let integer = table.new_variable(Kind::Integer);
+        let float = table.new_variable(Kind::Float);
+
+        assert!(!table.unify(integer, float));
+        assert_eq!(table.describe(integer), "an integer");
+        assert_eq!(table.describe(float), "a float");
+    }
+
+    #[test]
+    fn unknown_and_never_unify_with_everything() {
+        let mut table = InferenceTable::new();
+        let integer = table.new_variable(Kind::Integer);
+
+        for ty in [Type::Unknown, Type::Never] {
+            assert!(table.unify(ty, Type::Bool));
+            assert!(table.unify(Type::Void, ty));
+            assert!(table.unify(integer, ty));
+            assert!(table.require(ty, Kind::Float));
+        }
+
+        // which does not solve the variable
+        assert_eq!(table.describe(integer), "an integer");
+    }
+
+    #[test]
+    fn require_narrows_the_kind() {
+        let mut table = InferenceTable::new();
+        let value = table.new_variable(Kind::Value);
+
+        assert!(table.require(value, Kind::Numeric));
+        assert_eq!(table.describe(value), "a number");
+        assert!(!table.unify(value, Type::Bool));
+
+        assert!(table.require(value, Kind::Float));
+        assert!(!table.require(value, Kind::Integer));
+        assert_eq!(table.describe(value), "a float");
+    }
+
+    #[test]
+    fn require_checks_solved_types() {
+        let mut table = InferenceTable::new();
+
+        assert!(table.require(Type::I32, Kind::Integer));
+        assert!(table.require(Type::F32, Kind::Numeric));
+        assert!(!table.require(Type::Bool, Kind::Numeric));
+        assert!(!table.require(Type::Void, Kind::Value));
+    }
+
+    #[test]
+    fn could_be_does_not_narrow() {
+        let mut table = InferenceTable::new();
+        let numeric = table.new_variable(Kind::Numeric);
+
+        assert!(table.could_be(numeric, Kind::Float));
+        assert!(table.could_be(numeric, Kind::Integer));
+        assert_eq!(table.describe(numeric), "a number");
+    }
+
+    #[test]
+    fn finish_uses_the_default_of_the_kind() {
+        let mut table = InferenceTable::new();
+        let integer = table.new_variable(Kind::Integer);
+        let float = table.new_variable(Kind::Float);
+        let numeric = table.new_variable(Kind::Numeric);
+        let solved = table.new_variable(Kind::Value);
+        table.unify(solved, Type::Bool);
+
+        assert_eq!(table.finish(integer), Type::I64);
+        assert_eq!(table.finish(float), Type::F64);
+        assert_eq!(table.finish(numeric), Type::I64);
+        assert_eq!(table.finish(solved), Type::Bool);
+        assert_eq!(table.finish(Type::U32), Type::U32);
+    }
+}
@@ -0,0 +1,31 @@
+pub mod builtins;
+pub mod callgraph;
+pub mod infer;
+pub mod resolve;
+pub mod symbols;
+pub mod typeck;
+
+pub use builtins::Builtin;
+pub use symbols::SymbolTable;
+
+use crate::diagnostics::Diagnostic;
+use crate::parser::Module;
+
+/// Checks a parsed module before any LLVM IR is built and fills in the types
+/// of its expressions. Returns every problem found, codegen may assume the
+/// module is valid and fully typed when this returns no errors.
+pub fn analyse(module: &mut Module) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let symbols = SymbolTable::build(module, &mut diagnostics);
+    resolve::resolve_module(module, &symbols, &mut diagnostics);
+
+    // type errors in code that does not resolve are just noise
+    if diagnostics.iter().any(|d| d.is_error()) {
+        return diagnostics;
+    }
+
+    typeck::check_module(module, &mut diagnostics);
+
+    diagnostics
+}
@@ -0,0 +1,516 @@
+use super::{Builtin, SymbolTable};
+use crate::diagnostics::Diagnostic;
+use crate::parser::expression::FunctionArgument;
+use crate::parser::{
+    AssignExpression, BlockExpression, CallExpression, Expression, Module, Span, VariableExpression,
+};
+
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq)]
+enum BindingKind {
+    Parameter,
+    Let,
+    Var,
+}
+
+/// A local variable, `span` is where it was declared.
+#[derive(Clone, Copy)]
+struct LocalBinding {
+    span: Span,
+    kind: BindingKind,
+}
+
+/// Resolves every variable and call in the bodies of a module's functions.
+struct Resolver<'a> {
+    symbols: &'a SymbolTable,
+    scopes: Vec<HashMap<String, LocalBinding>>,
+    /// Number of loops around the expression being resolved.
+    loop_depth: usize,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+pub fn resolve_module(module: &Module, symbols: &SymbolTable, diagnostics: &mut Vec<Diagnostic>) {
+    let mut resolver = Resolver {
+        symbols,
+        scopes: Vec::new(),
+        loop_depth: 0,
+        diagnostics,
+    };
+
+    for expression in &module.expressions {
+        match expression {
+            Expression::Function(expr) => {
+                resolver.declare_arguments(&expr.arguments);
+                resolver.resolve_block(&expr.body);
+                resolver.scopes.pop();
+            }
+            Expression::ExternFunction(expr) => {
+                resolver.declare_arguments(&expr.arguments);
+                resolver.scopes.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Resolver<'_> {
+    /// Opens the scope of a function containing its arguments.
+    fn declare_arguments(&mut self, arguments: &[FunctionArgument]) {
+        let mut scope: HashMap<String, LocalBinding> = HashMap::new();
+
+        for argument in arguments {
+            if let Some(previous) = scope.get(&argument.binding_name) {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "identifier `{}` is bound more than once in this parameter list",
+                        argument.binding_name
+                    ))
+                    .with_primary(argument.span, "used as parameter more than once")
+                    .with_secondary(previous.span, "first bound here"),
+                );
+            } else {
+                scope.insert(
+                    argument.binding_name.clone(),
+                    LocalBinding {
+                        span: argument.span,
+                        kind: BindingKind::Parameter,
+                    },
+                );
+            }
+        }
+
+        self.scopes.push(scope);
+    }
+
+    fn lookup_binding(&self, name: &str) -> Option<LocalBinding> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+    }
+
+    /// Every block is a scope, bindings declared in it are gone at its end.
+    fn resolve_block(&mut self, block: &BlockExpression) {
+        self.scopes.push(HashMap::new());
+
+        for expression in &block.expressions {
+            self.resolve_expression(expression);
+        }
+
+        self.scopes.pop();
+    }
+
+    fn resolve_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Binary(expr) => {
+                self.resolve_expression(&expr.left);
+
+                // the right side of `&&` and `||` may not run, so what it
+                // binds is gone after it
+                if expr.operator.is_logical() {
+                    self.scopes.push(HashMap::new());
+                    self.resolve_expression(&expr.right);
+                    self.scopes.pop();
+                } else {
+                    self.resolve_expression(&expr.right);
+                }
+            }
+            Expression::Unary(expr) => self.resolve_expression(&expr.operand),
+            Expression::Cast(expr) => self.resolve_expression(&expr.value),
+            Expression::NumberLiteral(_)
+            | Expression::BooleanLiteral(_)
+            | Expression::StringLiteral(_) => {}
+            Expression::Variable(expr) => self.resolve_variable(expr),
+            Expression::Call(expr) => self.resolve_call(expr),
+            Expression::If(expr) => {
+                self.resolve_expression(&expr.condition);
+                self.resolve_block(&expr.then_block);
+                if let Some(else_block) = &expr.else_block {
+                    self.resolve_block(else_block);
+                }
+            }
+            Expression::While(expr) => {
+                self.resolve_expression(&expr.condition);
+                self.loop_depth += 1;
+                self.resolve_block(&expr.body);
+                self.loop_depth -= 1;
+            }
+            Expression::Let(expr) => {
+                // the value cannot refer to the binding it initialises
+                self.resolve_expression(&expr.value);
+
+                let kind = if expr.mutable {
+                    BindingKind::Var
+                } else {
+                    BindingKind::Let
+                };
+                self.scopes.last_mut().unwrap().insert(
+                    expr.binding.clone(),
+                    LocalBinding {
+                        span: expr.binding_span,
+                        kind,
+                    },
+                );
+            }
+            Expression::Assign(expr) => self.resolve_assign(expr),
+            Expression::LoopControl(expr) => {
+                if self.loop_depth == 0 {
+                    self.diagnostics.push(
+                        Diagnostic::error(format!("`{}` outside of a loop", expr.keyword()))
+                            .with_primary(
+                                expr.span,
+                                format!("cannot `{}` outside of a loop", expr.keyword()),
+                            ),
+                    );
+                }
+            }
+            Expression::Return(expr) => {
+                if let Some(value) = &expr.value {
+                    self.resolve_expression(value);
+                }
+            }
+            Expression::Function(_) | Expression::ExternFunction(_) => {
+                self.diagnostics.push(
+                    Diagnostic::error("functions can only be declared at the top level")
+                        .with_primary(expression.span(), "nested declaration"),
+                );
+            }
+        }
+    }
+
+    fn resolve_variable(&mut self, expression: &VariableExpression) {
+        if self.lookup_binding(&expression.binding).is_some() {
+            return;
+        }
+
+        let mut diagnostic = Diagnostic::error(format!(
+            "cannot find value `{}` in this scope",
+            expression.binding
+        ))
+        .with_primary(expression.span, "not found in this scope");
+
+        if let Some(function) = self.symbols.lookup(&expression.binding) {
+            diagnostic = diagnostic
+                .with_secondary(function.span, "a function with this name exists")
+                .with_note(format!(
+                    "functions are not values, call it with `{}(...)`",
+                    expression.binding
+                ));
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn resolve_assign(&mut self, expression: &AssignExpression) {
+        self.resolve_expression(&expression.value);
+
+        let binding = match self.lookup_binding(&expression.binding) {
+            Some(binding) => binding,
+            None => {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "cannot find value `{}` in this scope",
+                        expression.binding
+                    ))
+                    .with_primary(expression.binding_span, "not found in this scope")
+                    .with_note(format!(
+                        "declare it with `var {} = ...` to assign to it later",
+                        expression.binding
+                    )),
+                );
+                return;
+            }
+        };
+
+        let diagnostic = match binding.kind {
+            BindingKind::Var => return,
+            BindingKind::Let => Diagnostic::error(format!(
+                "cannot assign twice to immutable variable `{}`",
+                expression.binding
+            ))
+            .with_primary(expression.span, "cannot assign twice to immutable variable")
+            .with_secondary(
+                binding.span,
+                format!("first assignment to `{}`", expression.binding),
+            )
+            .with_note(format!(
+                "declare it with `var {}` to make it mutable",
+                expression.binding
+            )),
+            BindingKind::Parameter => Diagnostic::error(format!(
+                "cannot assign to immutable argument `{}`",
+                expression.binding
+            ))
+            .with_primary(expression.span, "cannot assign to immutable argument")
+            .with_secondary(binding.span, "argument declared here")
+            .with_note(format!(
+                "copy it into a mutable variable with `var {0} = {0}`",
+                expression.binding
+            )),
+        };
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    fn resolve_call(&mut self, expression: &CallExpression) {
+        for arg in &expression.args {
+            self.resolve_expression(arg);
+        }
+
+        if let Some(builtin) = Builtin::from_name(&expression.function_name) {
+            let arity = builtin.arity();
+            if arity != expression.args.len() {
+                self.diagnostics.push(arity_mismatch(expression, arity));
+            }
+
+            return;
+        }
+
+        let function = match self.symbols.lookup(&expression.function_name) {
+            Some(function) => function,
+            None => {
+                let mut diagnostic = Diagnostic::error(format!(
+                    "cannot find function `{}` in this module",
+                    expression.function_name
+                ))
+                .with_primary(expression.span, "not found in this module");
+
+                if let Some(binding) = self.lookup_binding(&expression.function_name) {
+                    diagnostic = diagnostic
+                        .with_secondary(binding.span, "this is a variable, not a function");
+                }
+
+                self.diagnostics.push(diagnostic);
+                return;
+            }
+        };
+
+        if function.arity != expression.args.len() {
+            let kind = if function.is_extern {
+                "extern function"
+            } else {
+                "function"
+            };
+
+            self.diagnostics.push(
+                arity_mismatch(expression, function.arity)
+                    .with_secondary(function.span, format!("{} defined here", kind)),
+            );
+        }
+    }
+}
+
+fn arity_mismatch(expression: &CallExpression, arity: usize) -> Diagnostic {
+    Diagnostic::error(format!(
+        "this function takes {} argument{} but {} argument{} supplied",
+        arity,
+        plural(arity),
+        expression.args.len(),
+        if expression.args.len() == 1 {
+            " was"
+        } else {
+            "s were"
+        }
+    ))
+    .with_primary(
+        expression.span,
+        format!("expected {} argument{}", arity, plural(arity)),
+    )
+}
+
+fn plural(count: usize) -> &'static str {
+    if count == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::parser::tokenizer::Tokenizer;
+
+    /// The message of every diagnostic reported while resolving `source`,
+    /// which must parse, with the source text its primary label points at.
+    fn resolve(source: &str) -> Vec<(String, &str)> {
+        let mut tokenizer = Tokenizer::new(source);
+        tokenizer.parse();
+        let mut parser = Parser::new(&tokenizer.tokens);
+        parser.parse_module();
+        assert!(parser.diagnostics.is_empty(), "{:?}", parser.diagnostics);
+
+        let mut diagnostics = Vec::new();
+        let symbols = SymbolTable::build(&parser.module, &mut diagnostics);
+        resolve_module(&parser.module, &symbols, &mut diagnostics);
+
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| {
+                let span = diagnostic.primary_span().unwrap();
+                (diagnostic.message, &source[span.start..span.end])
+            })
+            .collect()
+    }
+
+    fn single_error(source: &str) -> (String, &str) {
+        let mut errors = resolve(source);
+        assert_eq!(errors.len(), 1, "{}: {:?}", source, errors);
+        errors.remove(0)
+    }
+
+    #[test]
+    fn valid_program_resolves() {
+        let source = "
+            defextern print_digit(x: i64)
+            fn square(x) do x * x end
+            fn main do
+                var total = 0
+                let limit = 3
+                while total < limit do
+                    total = total + square(limit)
+                    if total > 5 do break else continue end
+                end
+                print_digit(total)
+            end
+        ";
+        assert_eq!(resolve(source), []);
+    }
+
+    #[test]
+    fn unknown_value() {
+        assert_eq!(
+            single_error("fn main do y end"),
+            ("cannot find value `y` in this scope".to_string(), "y")
+        );
+
+        // a binding is gone at the end of its block
+        assert_eq!(
+            single_error("fn main do if true do let y = 1 end y end"),
+            ("cannot find value `y` in this scope".to_string(), "y")
+        );
+    }
+
+    #[test]
+    fn function_used_as_a_value() {
+        assert_eq!(
+            single_error("fn f do 1 end fn main do f end"),
+            ("cannot find value `f` in this scope".to_string(), "f")
+        );
+    }
+
+    #[test]
+    fn unknown_function() {
+        assert_eq!(
+            single_error("fn main do g(1) end"),
+            (
+                "cannot find function `g` in this module".to_string(),
+                "g(1)"
+            )
+        );
+    }
+
+    #[test]
+    fn variable_called_as_a_function() {
+        let (message, primary) = single_error("fn main do let g = 1 \n g(1) end");
+        assert_eq!(message, "cannot find function `g` in this module");
+        assert_eq!(primary, "g(1)");
+    }
+
+    #[test]
+    fn arity_mismatch() {
+        assert_eq!(
+            single_error("fn f(a, b) do a end fn main do f(1) end"),
+            (
+                "this function takes 2 arguments but 1 argument was supplied".to_string(),
+                "f(1)"
+            )
+        );
+        assert_eq!(
+            single_error("fn main do to_int(1.5, 2) end"),
+            (
+                "this function takes 1 argument but 2 arguments were supplied".to_string(),
+                "to_int(1.5, 2)"
+            )
+        );
+    }
+
+    #[test]
+    fn duplicate_parameter() {
+        assert_eq!(
+            single_error("fn f(a, b, a) do a end"),
+            (
+                "identifier `a` is bound more than once in this parameter list".to_string(),
+                "a"
+            )
+        );
+    }
+
+    #[test]
+    fn assignment_to_an_immutable_binding() {
+        assert_eq!(
+            single_error("fn main do let x = 1 \n x = 2 end"),
+            (
+                "cannot assign twice to immutable variable `x`".to_string(),
+                "x = 2"
+            )
+        );
+        assert_eq!(
+            single_error("fn f(x) do x = 2 end"),
+            (
+                "cannot assign to immutable argument `x`".to_string(),
+                "x = 2"
+            )
+        );
+        assert_eq!(
+            single_error("fn main do x = 2 end"),
+            ("cannot find value `x` in this scope".to_string(), "x")
+        );
+    }
+
+    #[test]
+    fn loop_control_outside_of_a_loop() {
+        assert_eq!(
+            single_error("fn main do break end"),
+            ("`break` outside of a loop".to_string(), "break")
+        );
+
+        // a loop in another function does not count
+        let source = "fn f do while true do 1 end end fn main do continue end";
+        assert_eq!(
+            single_error(source),
+            ("`continue` outside of a loop".to_string(), "continue")
+        );
+    }
+
+    #[test]
+    fn nested_declaration() {
+        // the parser never nests declarations, so the module is put together
+        // by hand
+        let source = "fn main do 1 end fn inner do 2 end";
+        let mut tokenizer = Tokenizer::new(source);
+        tokenizer.parse();
+        let mut parser = Parser::new(&tokenizer.tokens);
+        parser.parse_module();
+
+        let inner = parser.module.expressions.pop().unwrap();
+        let inner_span = inner.span();
+        match &mut parser.module.expressions[0] {
+            Expression::Function(main) => main.body.expressions.push(inner),
+            expr => panic!("not a function: {:?}", expr),
+        }
+
+        let mut diagnostics = Vec::new();
+        let symbols = SymbolTable::build(&parser.module, &mut diagnostics);
+        resolve_module(&parser.module, &symbols, &mut diagnostics);
+
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(
+            diagnostics[0].message,
+            "functions can only be declared at the top level"
+        );
+        assert_eq!(diagnostics[0].primary_span(), Some(inner_span));
+    }
+}
@@ -0,0 +1,143 @@
+use super::Builtin;
+use crate::codegen::runtime::PANIC_FUNCTION;
+use crate::diagnostics::Diagnostic;
+use crate::parser::{Expression, Module, Span};
+
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub struct FunctionSymbol {
+    pub name: String,
+    pub arity: usize,
+    pub span: Span,
+    pub is_extern: bool,
+}
+
+/// Every function and extern declared at the top level of a module.
+#[derive(Debug)]
+pub struct SymbolTable {
+    functions: HashMap<String, FunctionSymbol>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable {
+            functions: HashMap::new(),
+        }
+    }
+
+    pub fn build(module: &Module, diagnostics: &mut Vec<Diagnostic>) -> Self {
+        let mut table = SymbolTable::new();
+
+        for expression in &module.expressions {
+            let symbol = match expression {
+                Expression::Function(expr) => FunctionSymbol {
+                    name: expr.name.clone(),
+                    arity: expr.arguments.len(),
+                    span: expr.name_span,
+                    is_extern: false,
+                },
+                Expression::ExternFunction(expr) => FunctionSymbol {
+                    name: expr.name.clone(),
+                    arity: expr.arguments.len(),
+                    span: expr.name_span,
+                    is_extern: true,
+                },
+                expr => {
+                    diagnostics.push(
+                        Diagnostic::error("expected a function or extern declaration")
+                            .with_primary(expr.span(), "not allowed at the top level"),
+                    );
+                    continue;
+                }
+            };
+
+            if let Err(diagnostic) = table.declare(symbol) {
+                diagnostics.push(diagnostic);
+            }
+        }
+
+        table
+    }
+
+    pub fn declare(&mut self, symbol: FunctionSymbol) -> Result<(), Diagnostic> {
+        if Builtin::from_name(&symbol.name).is_some() {
+            return Err(Diagnostic::error(format!(
+                "the name `{}` is reserved for a builtin function",
+                symbol.name
+            ))
+            .with_primary(symbol.span, "cannot be redefined"));
+        }
+
+        if symbol.name == PANIC_FUNCTION {
+            return Err(Diagnostic::error(format!(
+                "the name `{}` is reserved for the runtime",
+                symbol.name
+            ))
+            .with_primary(symbol.span, "cannot be redefined"));
+        }
+
+        if let Some(previous) = self.functions.get(&symbol.name) {
+            return Err(Diagnostic::error(format!(
+                "the function `{}` is defined multiple times",
+                symbol.name
+            ))
+            .with_primary(symbol.span, format!("`{}` redefined here", symbol.name))
+            .with_secondary(
+                previous.span,
+                format!("previous definition of `{}` here", symbol.name),
+            ));
+        }
+
+        self.functions.insert(symbol.name.clone(), symbol);
+        Ok(())
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<&FunctionSymbol> {
+        self.functions.get(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::parser::tokenizer::Tokenizer;
+
+    /// The messages of the diagnostics reported while declaring the
+    /// functions of `source`, which must parse.
+    fn declare(source: &str) -> Vec<String> {
+        let mut tokenizer = Tokenizer::new(source);
+        tokenizer.parse();
+        let mut parser = Parser::new(&tokenizer.tokens);
+        parser.parse_module();
+        assert!(parser.diagnostics.is_empty(), "{:?}", parser.diagnostics);
+
+        let mut diagnostics = Vec::new();
+        SymbolTable::build(&parser.module, &mut diagnostics);
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn runtime_panic_function_is_reserved() {
+        let reserved = vec!["the name `ijssel_panic` is reserved for the runtime".to_string()];
+
+        assert_eq!(declare("fn ijssel_panic(a) do a end"), reserved);
+        assert_eq!(declare("defextern ijssel_panic(a: i64) -> i64"), reserved);
+    }
+
+    #[test]
+    fn functions_are_declared_once() {
+        assert_eq!(
+            declare("fn f do 1 end fn f do 2 end"),
+            vec!["the function `f` is defined multiple times".to_string()]
+        );
+        assert_eq!(
+            declare("fn to_int(x) do x end"),
+            vec!["the name `to_int` is reserved for a builtin function".to_string()]
+        );
+    }
+}
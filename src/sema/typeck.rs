@@ -0,0 +1,1111 @@
+use super::callgraph;
+use super::infer::{InferenceTable, Kind};
+use super::Builtin;
+use crate::diagnostics::Diagnostic;
+use crate::parser::expression::NumberValue;
+use crate::parser::tokenizer::UnaryOperator;
+use crate::parser::{
+    AssignExpression, BinaryExpression, BlockExpression, CallExpression, CastExpression,
+    Expression, FunctionExpression, IfExpression, LetExpression, Module, NumberLiteralExpression,
+    ReturnExpression, Span, Type, UnaryExpression,
+};
+
+use std::collections::{HashMap, HashSet};
+
+/// The function whose body is being checked, which `return` leaves.
+#[derive(Clone, Debug, Default)]
+struct CurrentFunction {
+    name: String,
+    name_span: Span,
+    /// Whether the return type was annotated rather than inferred.
+    annotated: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Signature {
+    parameters: Vec<Type>,
+    /// Where the parameters are declared, empty for builtins.
+    parameter_spans: Vec<Span>,
+    return_type: Type,
+    /// The type variables a generic function can be used with any type for,
+    /// shared by the functions it calls recursively.
+    quantified: Vec<u32>,
+}
+
+/// A copy of a generic function for the types of one of its uses.
+struct Instance {
+    function: String,
+    name: String,
+    substitution: HashMap<u32, Type>,
+}
+
+/// Assigns a type to every expression in the module and checks that they are
+/// used consistently.
+///
+/// Types that are not annotated are inferred from how values are used by
+/// unifying type variables. Functions are checked before their callers, and
+/// the type variables their body leaves open make them generic: every call
+/// gets new variables for those, so `fn id(x) do x end` can be called with
+/// both `1` and `true`. Functions that call each other recursively are
+/// checked together and only generic at calls from outside their group.
+/// Externs have one type at every call.
+///
+/// Once everything is checked a generic function is copied for every
+/// combination of types it is used with. What nothing decides on is `i64`,
+/// or `f64` for float literals, and the copy with those types keeps the name
+/// of the function. The others are named after their types, like `id.bool`.
+///
+/// A `void` function discards the value of its body, and of its `return`
+/// expressions.
+pub fn check_module(module: &mut Module, diagnostics: &mut Vec<Diagnostic>) {
+    let mut table = InferenceTable::new();
+    let mut signatures: HashMap<String, Signature> = HashMap::new();
+    let mut monomorphic: Vec<Type> = Vec::new();
+
+    for expression in module.expressions.iter_mut() {
+        let (name, arguments, return_type, is_extern) = match expression {
+            Expression::Function(expr) => {
+                (&expr.name, &mut expr.arguments, expr.return_type, false)
+            }
+            Expression::ExternFunction(expr) => {
+                (&expr.name, &mut expr.arguments, expr.return_type, true)
+            }
+            _ => continue,
+        };
+
+        for argument in arguments.iter_mut() {
+            if !argument.ty.is_known() {
+                argument.ty = table.new_variable(Kind::Value);
+            }
+        }
+
+        let return_type = if return_type.is_known() {
+            return_type
+        } else {
+            table.new_variable(Kind::Any)
+        };
+
+        let parameters: Vec<Type> = arguments.iter().map(|arg| arg.ty).collect();
+        if is_extern {
+            monomorphic.extend(parameters.iter().copied());
+            monomorphic.push(return_type);
+        }
+
+        signatures.insert(
+            name.clone(),
+            Signature {
+                parameters,
+                parameter_spans: arguments.iter().map(|arg| arg.span).collect(),
+                return_type,
+                quantified: Vec::new(),
+            },
+        );
+    }
+
+    let mut checker = TypeChecker {
+        table,
+        signatures,
+        monomorphic,
+        function: CurrentFunction::default(),
+        scopes: Vec::new(),
+        substitution: HashMap::new(),
+        requested: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    for group in callgraph::function_groups(module) {
+        let mut names = Vec::with_capacity(group.len());
+        for i in group {
+            if let Expression::Function(expr) = &mut module.expressions[i] {
+                checker.check_function(expr);
+                names.push(expr.name.clone());
+            }
+        }
+
+        checker.generalise(&names);
+    }
+
+    // only now that every function is checked are all types known, the
+    // bodies are copied before any of them is changed
+    let originals: HashMap<String, FunctionExpression> = module
+        .expressions
+        .iter()
+        .filter_map(|expression| match expression {
+            Expression::Function(expr) => Some((expr.name.clone(), (**expr).clone())),
+            _ => None,
+        })
+        .collect();
+
+    for expression in module.expressions.iter_mut() {
+        match expression {
+            Expression::Function(expr) => {
+                let name = expr.name.clone();
+                let substitution = checker.default_substitution(&name);
+                checker.finish_function(expr, &name, substitution);
+            }
+            Expression::ExternFunction(expr) => {
+                for argument in expr.arguments.iter_mut() {
+                    argument.ty = checker.table.finish(argument.ty);
+                }
+                expr.return_type = checker
+                    .table
+                    .finish(checker.signatures[&expr.name].return_type);
+            }
+            _ => {}
+        }
+    }
+
+    // finishing an instance can request more, of functions checked earlier
+    let mut instances: HashSet<String> = originals.keys().cloned().collect();
+    while let Some(instance) = checker.requested.pop() {
+        if !instances.insert(instance.name.clone()) {
+            continue;
+        }
+
+        let mut function = originals[&instance.function].clone();
+        function.name = instance.name;
+        checker.finish_function(&mut function, &instance.function, instance.substitution);
+        module
+            .expressions
+            .push(Expression::Function(Box::new(function)));
+    }
+
+    // the copies of a generic function repeat its mistakes
+    let mut reported: Vec<(String, Vec<Span>)> = Vec::new();
+    checker.diagnostics.retain(|diagnostic| {
+        let key = (
+            diagnostic.message.clone(),
+            diagnostic.labels.iter().map(|label| label.span).collect(),
+        );
+        let first = !reported.contains(&key);
+        reported.push(key);
+        first
+    });
+
+    diagnostics.append(&mut checker.diagnostics);
+}
+
+/// The span of the expression a block evaluates to.
+fn block_value_span(block: &BlockExpression) -> Span {
+    block
+        .expressions
+        .last()
+        .map(|expression| expression.span())
+        .unwrap_or(block.span)
+}
+
+struct TypeChecker {
+    table: InferenceTable,
+    signatures: HashMap<String, Signature>,
+    /// The types of the parameters and results of externs, which are never
+    /// generic.
+    monomorphic: Vec<Type>,
+    function: CurrentFunction,
+    /// The types of the variables in scope, one map per block.
+    scopes: Vec<HashMap<String, Type>>,
+    /// The types of the instance being finished, for the variables of its
+    /// function.
+    substitution: HashMap<u32, Type>,
+    /// Instances used by the ones finished so far.
+    requested: Vec<Instance>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl TypeChecker {
+    fn lookup_binding(&self, name: &str) -> Type {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name).copied())
+            .unwrap_or(Type::Unknown)
+    }
+
+    fn check_function(&mut self, expression: &mut FunctionExpression) {
+        self.scopes = vec![expression
+            .arguments
+            .iter()
+            .map(|arg| (arg.binding_name.clone(), arg.ty))
+            .collect()];
+
+        // the parser leaves the return type unknown unless it is annotated
+        self.function = CurrentFunction {
+            name: expression.name.clone(),
+            name_span: expression.name_span,
+            annotated: expression.return_type.is_known(),
+        };
+
+        let body_type = self.check_block(&mut expression.body);
+        self.check_returned(expression.body.span, body_type);
+    }
+
+    /// Makes the functions of a group generic in the type variables that
+    /// checking them left open, except those shared with an extern.
+    fn generalise(&mut self, group: &[String]) {
+        let fixed: Vec<Type> = self
+            .monomorphic
+            .iter()
+            .map(|ty| self.table.resolve(*ty))
+            .collect();
+
+        let mut quantified = Vec::new();
+        for name in group {
+            let signature = &self.signatures[name];
+            let types = signature.parameters.iter().chain([&signature.return_type]);
+
+            for ty in types {
+                match self.table.resolve(*ty) {
+                    Type::Variable(variable)
+                        if !fixed.contains(&Type::Variable(variable))
+                            && !quantified.contains(&variable) =>
+                    {
+                        quantified.push(variable)
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for name in group {
+            self.signatures.get_mut(name).unwrap().quantified = quantified.clone();
+        }
+    }
+
+    /// Replaces the variables a generic function is generic in by the types
+    /// of one of its uses.
+    fn substitute(&self, ty: Type, quantified: &[u32], types: &[Type]) -> Type {
+        match self.table.resolve(ty) {
+            Type::Variable(variable) => quantified
+                .iter()
+                .position(|quantified| *quantified == variable)
+                .map(|i| types[i])
+                .unwrap_or(Type::Variable(variable)),
+            ty => ty,
+        }
+    }
+
+    /// Checks that a function returns a value of its return type, either at
+    /// the end of its body or with `return`.
+    fn check_returned(&mut self, span: Span, ty: Type) {
+        let return_type = self.signatures[&self.function.name].return_type;
+
+        if return_type == Type::Void || self.table.unify(return_type, ty) {
+            return;
+        }
+
+        let reason = if self.function.annotated {
+            "is declared to return"
+        } else {
+            "is inferred to return"
+        };
+
+        self.diagnostics.push(
+            Diagnostic::error("mismatched types")
+                .with_primary(
+                    span,
+                    format!(
+                        "expected {}, found {}",
+                        self.table.describe(return_type),
+                        self.table.describe(ty)
+                    ),
+                )
+                .with_secondary(
+                    self.function.name_span,
+                    format!(
+                        "`{}` {} {}",
+                        self.function.name,
+                        reason,
+                        self.table.describe(return_type)
+                    ),
+                ),
+        );
+    }
+
+    /// `return` without a value returns `void`.
+    fn check_return(&mut self, expression: &mut ReturnExpression) -> Type {
+        match &mut expression.value {
+            Some(value) => {
+                let ty = self.check_expression(value);
+                self.check_returned(value.span(), ty);
+            }
+            None => self.check_returned(expression.span, Type::Void),
+        }
+
+        Type::Never
+    }
+
+    /// A block has the type of its last expression, or `!` when any of its
+    /// expressions leaves it early. Expressions after that are never
+    /// evaluated, which is reported once per block.
+    fn check_block(&mut self, block: &mut BlockExpression) -> Type {
+        let mut last_type = Type::I64;
+        let mut diverges = false;
+        // the expression that diverged, until the one after it is reported
+        let mut diverging: Option<Span> = None;
+
+        self.scopes.push(HashMap::new());
+        for expression in block.expressions.iter_mut() {
+            if let Some(span) = diverging.take() {
+                self.diagnostics.push(
+                    Diagnostic::warning("unreachable expression")
+                        .with_primary(expression.span(), "unreachable expression")
+                        .with_secondary(span, "any code following this expression is unreachable"),
+                );
+            }
+
+            last_type = self.check_expression(expression);
+            if last_type == Type::Never && !diverges {
+                diverges = true;
+                diverging = Some(expression.span());
+            }
+        }
+        self.scopes.pop();
+
+        if diverges {
+            Type::Never
+        } else {
+            last_type
+        }
+    }
+
+    /// Returns the type of an expression, which may still be a type
+    /// variable. Expressions with an error are `Unknown`, which unifies with
+    /// everything so that one mistake does not cascade.
+    fn check_expression(&mut self, expression: &mut Expression) -> Type {
+        match expression {
+            Expression::NumberLiteral(expr) => self.check_number_literal(expr),
+            Expression::BooleanLiteral(_) => Type::Bool,
+            Expression::StringLiteral(_) => Type::Ptr,
+            Expression::Variable(expr) => {
+                expr.ty = self.lookup_binding(&expr.binding);
+                expr.ty
+            }
+            Expression::Let(expr) => self.check_let(expr),
+            Expression::Assign(expr) => self.check_assign(expr),
+            Expression::Binary(expr) => self.check_binary(expr),
+            Expression::Unary(expr) => self.check_unary(expr),
+            Expression::Cast(expr) => {
+                let value = self.check_expression(&mut expr.value);
+                self.table.require(value, Kind::Value);
+                expr.target
+            }
+            Expression::Call(expr) => self.check_call(expr),
+            Expression::If(expr) => self.check_if(expr),
+            Expression::While(expr) => {
+                self.check_condition(&mut expr.condition);
+                self.check_block(&mut expr.body);
+                Type::I64
+            }
+            Expression::LoopControl(_) => Type::Never,
+            Expression::Return(expr) => self.check_return(expr),
+            Expression::Function(_) | Expression::ExternFunction(_) => Type::Unknown,
+        }
+    }
+
+    /// Literals without a suffix can be any integer or float type, their
+    /// value is checked once that type is known.
+    fn check_number_literal(&mut self, expression: &mut NumberLiteralExpression) -> Type {
+        if !expression.ty.is_known() {
+            expression.ty = match expression.number {
+                NumberValue::Integer(_) => self.table.new_variable(Kind::Integer),
+                NumberValue::Float(_) => self.table.new_variable(Kind::Float),
+            };
+        }
+
+        expression.ty
+    }
+
+    fn check_unary(&mut self, expression: &mut UnaryExpression) -> Type {
+        let operand = self.check_expression(&mut expression.operand);
+
+        let valid = match expression.operator {
+            UnaryOperator::Neg => self.table.require(operand, Kind::Numeric),
+            UnaryOperator::Not => self.table.unify(operand, Type::Bool),
+            UnaryOperator::BitNot => self.table.require(operand, Kind::Integer),
+        };
+
+        expression.ty = if valid {
+            operand
+        } else {
+            let note = match expression.operator {
+                UnaryOperator::Neg => "only numbers can be negated",
+                UnaryOperator::Not => {
+                    "`!` negates a `bool`, use `~` to flip the bits of an integer"
+                }
+                UnaryOperator::BitNot => {
+                    "`~` flips the bits of an integer, use `!` to negate a `bool`"
+                }
+            };
+
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "cannot apply unary operator `{}` to {}",
+                    expression.operator,
+                    self.table.describe(operand)
+                ))
+                .with_primary(
+                    expression.span,
+                    format!("cannot apply unary operator `{}`", expression.operator),
+                )
+                .with_note(note),
+            );
+            Type::Unknown
+        };
+
+        expression.ty
+    }
+
+    fn check_binary(&mut self, expression: &mut BinaryExpression) -> Type {
+        if expression.operator.is_logical() {
+            self.check_condition(&mut expression.left);
+
+            // like the resolver, the right side is a scope of its own
+            self.scopes.push(HashMap::new());
+            self.check_condition(&mut expression.right);
+            self.scopes.pop();
+
+            expression.ty = Type::Bool;
+            return expression.ty;
+        }
+
+        let left = self.check_expression(&mut expression.left);
+        let right = self.check_expression(&mut expression.right);
+        let operator = expression.operator;
+
+        if operator.is_shift() {
+            return self.check_shift(expression, left, right);
+        }
+
+        let operands_valid = if !self.table.unify(left, right) {
+            let mut diagnostic = Diagnostic::error(format!(
+                "cannot apply `{}` to {} and {}",
+                operator,
+                self.table.describe(left),
+                self.table.describe(right)
+            ))
+            .with_primary(
+                expression.left.span(),
+                format!("this is {}", self.table.describe(left)),
+            )
+            .with_primary(
+                expression.right.span(),
+                format!("this is {}", self.table.describe(right)),
+            );
+
+            if self.table.could_be(left, Kind::Numeric) && self.table.could_be(right, Kind::Numeric)
+            {
+                diagnostic = diagnostic.with_note("use `as` to convert between numeric types");
+            }
+
+            self.diagnostics.push(diagnostic);
+            false
+        } else if self.table.resolve(left) == Type::Void {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot apply `{}` to `void`", operator))
+                    .with_primary(expression.span, "these do not produce a value"),
+            );
+            false
+        } else if operator.is_comparison() {
+            self.table.require(left, Kind::Value)
+        } else if operator.is_bitwise() {
+            // both integers and `bool` are allowed, which is checked once
+            // inference is done
+            self.table.require(left, Kind::Value)
+        } else if !self.table.require(left, Kind::Numeric) {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "cannot apply `{}` to {}",
+                    operator,
+                    self.table.describe(left)
+                ))
+                .with_primary(
+                    expression.span,
+                    format!("these are {}", self.table.describe(left)),
+                )
+                .with_note("arithmetic is only defined for numeric types"),
+            );
+            false
+        } else {
+            true
+        };
+
+        // a comparison is a `bool` even when its operands are wrong, so that
+        // the mistake is not reported again where the result is used
+        expression.ty = if operator.is_comparison() {
+            Type::Bool
+        } else if operands_valid {
+            left
+        } else {
+            Type::Unknown
+        };
+
+        expression.ty
+    }
+
+    /// Both sides of a shift are integers, but not necessarily of the same
+    /// type. The result has the type of the value being shifted.
+    fn check_shift(&mut self, expression: &mut BinaryExpression, left: Type, right: Type) -> Type {
+        let mut valid = true;
+
+        for (operand, ty) in [(&expression.left, left), (&expression.right, right)] {
+            if !self.table.require(ty, Kind::Integer) {
+                self.diagnostics.push(
+                    Diagnostic::error(format!(
+                        "cannot apply `{}` to {}",
+                        expression.operator,
+                        self.table.describe(ty)
+                    ))
+                    .with_primary(
+                        operand.span(),
+                        format!("this is {}", self.table.describe(ty)),
+                    )
+                    .with_note("shifts are only defined for integers"),
+                );
+                valid = false;
+            }
+        }
+
+        expression.ty = if valid { left } else { Type::Unknown };
+        expression.ty
+    }
+
+    /// A binding has the annotated type if there is one, otherwise the type
+    /// of its value. Both `let` and assignment evaluate to the value.
+    fn check_let(&mut self, expression: &mut LetExpression) -> Type {
+        let mut value = self.check_expression(&mut expression.value);
+
+        if value == Type::Never {
+            expression.ty = Type::Never;
+            return expression.ty;
+        }
+
+        // the binding is left untyped so that its uses are not reported too
+        if self.table.resolve(value) == Type::Void {
+            value = Type::Unknown;
+            self.diagnostics.push(
+                Diagnostic::error("cannot bind a value of type `void`")
+                    .with_primary(expression.value.span(), "this does not produce a value")
+                    .with_secondary(expression.binding_span, "expected a value for this binding"),
+            );
+        } else {
+            self.table.require(value, Kind::Value);
+        }
+
+        expression.ty = if expression.annotation.is_known() {
+            if !self.table.unify(expression.annotation, value) {
+                self.diagnostics.push(
+                    Diagnostic::error("mismatched types")
+                        .with_primary(
+                            expression.value.span(),
+                            format!(
+                                "expected `{}`, found {}",
+                                expression.annotation,
+                                self.table.describe(value)
+                            ),
+                        )
+                        .with_secondary(expression.binding_span, "expected due to this"),
+                );
+            }
+
+            expression.annotation
+        } else {
+            value
+        };
+
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(expression.binding.clone(), expression.ty);
+
+        expression.ty
+    }
+
+    fn check_assign(&mut self, expression: &mut AssignExpression) -> Type {
+        let value = self.check_expression(&mut expression.value);
+        let binding = self.lookup_binding(&expression.binding);
+
+        if value == Type::Never {
+            expression.ty = Type::Never;
+            return expression.ty;
+        }
+
+        if !self.table.unify(binding, value) {
+            self.diagnostics.push(
+                Diagnostic::error("mismatched types")
+                    .with_primary(
+                        expression.value.span(),
+                        format!(
+                            "expected {}, found {}",
+                            self.table.describe(binding),
+                            self.table.describe(value)
+                        ),
+                    )
+                    .with_secondary(
+                        expression.binding_span,
+                        format!(
+                            "`{}` is {}",
+                            expression.binding,
+                            self.table.describe(binding)
+                        ),
+                    ),
+            );
+        }
+
+        expression.ty = if binding.is_known() { binding } else { value };
+        expression.ty
+    }
+
+    fn check_condition(&mut self, condition: &mut Expression) {
+        let ty = self.check_expression(condition);
+
+        if !self.table.unify(Type::Bool, ty) {
+            self.diagnostics.push(
+                Diagnostic::error("mismatched types")
+                    .with_primary(
+                        condition.span(),
+                        format!("expected `bool`, found {}", self.table.describe(ty)),
+                    )
+                    .with_note("use a comparison such as `!= 0` to get a `bool`"),
+            );
+        }
+    }
+
+    /// Both branches must have the same type, unless one of them never
+    /// finishes. Without an `else` the value is zero when the condition does
+    /// not hold, like the value of an empty body.
+    fn check_if(&mut self, expression: &mut IfExpression) -> Type {
+        self.check_condition(&mut expression.condition);
+
+        let then_type = self.check_block(&mut expression.then_block);
+        let else_type = match &mut expression.else_block {
+            Some(else_block) => self.check_block(else_block),
+            None if then_type == Type::Never => Type::I64,
+            None => then_type,
+        };
+
+        expression.ty = if then_type == Type::Never {
+            else_type
+        } else if else_type == Type::Never || self.table.unify(then_type, else_type) {
+            then_type
+        } else {
+            let else_block = expression.else_block.as_ref().unwrap();
+            self.diagnostics.push(
+                Diagnostic::error("`if` and `else` have incompatible types")
+                    .with_primary(
+                        block_value_span(else_block),
+                        format!(
+                            "expected {}, found {}",
+                            self.table.describe(then_type),
+                            self.table.describe(else_type)
+                        ),
+                    )
+                    .with_secondary(
+                        block_value_span(&expression.then_block),
+                        format!("this is {}", self.table.describe(then_type)),
+                    ),
+            );
+            Type::Unknown
+        };
+
+        expression.ty
+    }
+
+    fn check_call(&mut self, expression: &mut CallExpression) -> Type {
+        let signature = if let Some(builtin) = Builtin::from_name(&expression.function_name) {
+            let (parameters, return_type) = builtin.signature(&mut self.table);
+            Signature {
+                parameters,
+                parameter_spans: Vec::new(),
+                return_type,
+                quantified: Vec::new(),
+            }
+        } else if let Some(signature) = self.signatures.get(&expression.function_name) {
+            let mut signature = signature.clone();
+
+            // a generic function can be used with other types at every call
+            if !signature.quantified.is_empty() {
+                let instance = self.table.instantiate(&signature.quantified);
+                for parameter in signature.parameters.iter_mut() {
+                    *parameter = self.substitute(*parameter, &signature.quantified, &instance);
+                }
+                signature.return_type =
+                    self.substitute(signature.return_type, &signature.quantified, &instance);
+                expression.instance = instance;
+            }
+
+            signature
+        } else {
+            Signature {
+                parameters: Vec::new(),
+                parameter_spans: Vec::new(),
+                return_type: Type::Unknown,
+                quantified: Vec::new(),
+            }
+        };
+
+        for (i, arg) in expression.args.iter_mut().enumerate() {
+            let arg_type = self.check_expression(arg);
+
+            if let Some(expected) = signature.parameters.get(i) {
+                if !self.table.unify(*expected, arg_type) {
+                    self.mismatched_argument(
+                        &expression.function_name,
+                        arg.span(),
+                        signature.parameter_spans.get(i).copied(),
+                        *expected,
+                        arg_type,
+                    );
+                }
+            }
+        }
+
+        expression.ty = signature.return_type;
+        expression.ty
+    }
+
+    fn mismatched_argument(
+        &mut self,
+        function_name: &str,
+        span: Span,
+        parameter_span: Option<Span>,
+        expected: Type,
+        found: Type,
+    ) {
+        let mut diagnostic = Diagnostic::error("mismatched types").with_primary(
+            span,
+            format!(
+                "expected {}, found {}",
+                self.table.describe(expected),
+                self.table.describe(found)
+            ),
+        );
+
+        if let Some(parameter_span) = parameter_span {
+            diagnostic = diagnostic.with_secondary(
+                parameter_span,
+                format!(
+                    "this parameter of `{}` is {}",
+                    function_name,
+                    self.table.describe(expected)
+                ),
+            );
+        }
+
+        if self.table.could_be(expected, Kind::Numeric) && self.table.could_be(found, Kind::Numeric)
+        {
+            diagnostic = diagnostic.with_note("use `as` to convert between numeric types");
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// The types of the default instance of a function, which keeps its name.
+    fn default_substitution(&self, function: &str) -> HashMap<u32, Type> {
+        self.signatures[function]
+            .quantified
+            .iter()
+            .map(|variable| (*variable, self.table.finish(Type::Variable(*variable))))
+            .collect()
+    }
+
+    /// Finishes an instance of a function: a copy of its body with the types
+    /// of the substitution for the variables it is generic in.
+    fn finish_function(
+        &mut self,
+        expression: &mut FunctionExpression,
+        function: &str,
+        substitution: HashMap<u32, Type>,
+    ) {
+        self.substitution = substitution;
+
+        self.finish_block(&mut expression.body);
+        for argument in expression.arguments.iter_mut() {
+            argument.ty = self.finish_type(argument.ty);
+        }
+        expression.return_type = self.finish_type(self.signatures[function].return_type);
+    }
+
+    /// Like `InferenceTable::finish`, but in the instance being finished.
+    fn finish_type(&self, ty: Type) -> Type {
+        match self.table.resolve(ty) {
+            Type::Variable(variable) => match self.substitution.get(&variable) {
+                Some(ty) => *ty,
+                None => self.table.finish(ty),
+            },
+            _ => self.table.finish(ty),
+        }
+    }
+
+    /// Points a call to a generic function at the instance for the types it
+    /// is called with, and requests that instance.
+    fn finish_call_instance(&mut self, expression: &mut CallExpression) {
+        let quantified = match self.signatures.get(&expression.function_name) {
+            Some(signature) if !signature.quantified.is_empty() => signature.quantified.clone(),
+            _ => return,
+        };
+
+        // a call within the group of the callee has no instance of its own,
+        // it uses the one of the caller
+        let types: Vec<Type> = if expression.instance.is_empty() {
+            quantified
+                .iter()
+                .map(|variable| self.finish_type(Type::Variable(*variable)))
+                .collect()
+        } else {
+            expression
+                .instance
+                .iter()
+                .map(|ty| self.finish_type(*ty))
+                .collect()
+        };
+
+        let default = self.default_substitution(&expression.function_name);
+        if quantified
+            .iter()
+            .zip(&types)
+            .all(|(variable, ty)| default[variable] == *ty)
+        {
+            return;
+        }
+
+        let name = format!(
+            "{}.{}",
+            expression.function_name,
+            types
+                .iter()
+                .map(|ty| ty.to_string())
+                .collect::<Vec<_>>()
+                .join(".")
+        );
+        self.requested.push(Instance {
+            function: std::mem::replace(&mut expression.function_name, name.clone()),
+            name,
+            substitution: quantified.into_iter().zip(types).collect(),
+        });
+    }
+
+    fn finish_block(&mut self, block: &mut BlockExpression) {
+        for expression in block.expressions.iter_mut() {
+            self.finish_expression(expression);
+        }
+    }
+
+    /// Replaces the type variables in an expression by the types inferred
+    /// for them.
+    fn finish_expression(&mut self, expression: &mut Expression) {
+        match expression {
+            Expression::NumberLiteral(expr) => {
+                expr.ty = self.finish_type(expr.ty);
+                self.check_literal_range(expr);
+            }
+            Expression::Variable(expr) => expr.ty = self.finish_type(expr.ty),
+            Expression::Let(expr) => {
+                self.finish_expression(&mut expr.value);
+                expr.ty = self.finish_type(expr.ty);
+            }
+            Expression::Assign(expr) => {
+                self.finish_expression(&mut expr.value);
+                expr.ty = self.finish_type(expr.ty);
+            }
+            Expression::Binary(expr) => {
+                self.finish_expression(&mut expr.left);
+                self.finish_expression(&mut expr.right);
+                expr.ty = self.finish_type(expr.ty);
+                self.check_bitwise_operands(expr);
+            }
+            Expression::Unary(expr) => {
+                self.finish_expression(&mut expr.operand);
+                expr.ty = self.finish_type(expr.ty);
+                self.check_unsigned_negation(expr);
+            }
+            Expression::Cast(expr) => {
+                self.finish_expression(&mut expr.value);
+                self.check_cast(expr);
+            }
+            Expression::Call(expr) => {
+                for arg in expr.args.iter_mut() {
+                    self.finish_expression(arg);
+                }
+                expr.ty = self.finish_type(expr.ty);
+                self.finish_call_instance(expr);
+            }
+            Expression::If(expr) => {
+                self.finish_expression(&mut expr.condition);
+                self.finish_block(&mut expr.then_block);
+                if let Some(else_block) = &mut expr.else_block {
+                    self.finish_block(else_block);
+                }
+                expr.ty = self.finish_type(expr.ty);
+            }
+            Expression::While(expr) => {
+                self.finish_expression(&mut expr.condition);
+                self.finish_block(&mut expr.body);
+            }
+            Expression::Return(expr) => {
+                if let Some(value) = &mut expr.value {
+                    self.finish_expression(value);
+                }
+            }
+            Expression::BooleanLiteral(_)
+            | Expression::StringLiteral(_)
+            | Expression::LoopControl(_)
+            | Expression::Function(_)
+            | Expression::ExternFunction(_) => {}
+        }
+    }
+
+    /// Only numbers can be cast, to any other numeric type, and `bool` can be
+    /// cast to an integer. The type of the value is only known for sure once
+    /// inference is done.
+    fn check_cast(&mut self, expression: &CastExpression) {
+        let from = expression.value.ty();
+        let to = expression.target;
+
+        let valid = !from.is_known()
+            || from == Type::Never
+            || from == to
+            || (from.is_numeric() && to.is_numeric())
+            || (from == Type::Bool && to.is_integer());
+
+        if !valid {
+            self.diagnostics.push(
+                Diagnostic::error(format!("cannot cast `{}` as `{}`", from, to))
+                    .with_primary(expression.span, "invalid cast")
+                    .with_note(
+                        "only numbers can be cast to other numeric types, and `bool` to integers",
+                    ),
+            );
+        }
+    }
+
+    fn check_bitwise_operands(&mut self, expression: &BinaryExpression) {
+        let ty = expression.ty;
+
+        let valid = !ty.is_known() || ty.is_integer() || matches!(ty, Type::Bool | Type::Never);
+
+        if expression.operator.is_bitwise() && !valid {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "cannot apply `{}` to `{}`",
+                    expression.operator, ty
+                ))
+                .with_primary(expression.span, format!("these are `{}`", ty))
+                .with_note("bitwise operators are only defined for integers and `bool`"),
+            );
+        }
+    }
+
+    /// Whether a negation is valid depends on the signedness of its operand,
+    /// which is only known once inference is done.
+    fn check_unsigned_negation(&mut self, expression: &UnaryExpression) {
+        if expression.operator == UnaryOperator::Neg
+            && expression.ty.is_integer()
+            && !expression.ty.is_signed_integer()
+        {
+            self.diagnostics.push(
+                Diagnostic::error(format!(
+                    "cannot apply unary operator `-` to `{}`",
+                    expression.ty
+                ))
+                .with_primary(expression.span, "cannot apply unary operator `-`")
+                .with_note("unsigned values cannot be negated"),
+            );
+        }
+    }
+
+    /// The value of a literal has to fit in the type it ended up with.
+    fn check_literal_range(&mut self, expression: &NumberLiteralExpression) {
+        let fits = match (expression.number, expression.ty.integer_range()) {
+            (NumberValue::Integer(integer), Some((min, max))) => {
+                if integer < min || integer > max {
+                    Err(format!(
+                        "the literal `{}` does not fit into the type `{}` whose range is `{}..={}`",
+                        integer, expression.ty, min, max
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+            (NumberValue::Float(float), _) => match expression.ty.float_max() {
+                Some(max) if float.abs() > max => Err(format!(
+                    "the literal is too large for `{}`, whose maximum is `{:e}`",
+                    expression.ty, max
+                )),
+                _ => Ok(()),
+            },
+            _ => Ok(()),
+        };
+
+        if let Err(note) = fits {
+            self.diagnostics.push(
+                Diagnostic::error(format!("literal out of range for `{}`", expression.ty))
+                    .with_primary(expression.span, "out of range")
+                    .with_note(note),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::{Diagnostic, Severity};
+    use crate::parser::parser::Parser;
+    use crate::parser::tokenizer::Tokenizer;
+
+    /// Every diagnostic sema reports for `source`, which must parse.
+    fn analyse(source: &str) -> Vec<Diagnostic> {
+        let mut tokenizer = Tokenizer::new(source);
+        tokenizer.parse();
+        let mut parser = Parser::new(&tokenizer.tokens);
+        parser.parse_module();
+        assert!(parser.diagnostics.is_empty(), "{:?}", parser.diagnostics);
+
+        crate::sema::analyse(&mut parser.module)
+    }
+
+    /// The source text of the labels of a diagnostic, the primary one first.
+    fn labelled<'a>(source: &'a str, diagnostic: &Diagnostic) -> Vec<&'a str> {
+        let mut labels: Vec<_> = diagnostic.labels.iter().collect();
+        labels.sort_by_key(|label| !label.primary);
+        labels
+            .into_iter()
+            .map(|label| &source[label.span.start..label.span.end])
+            .collect()
+    }
+
+    #[test]
+    fn code_after_return_is_unreachable() {
+        let source = "fn f -> i64 do\n    return 1\n    2\nend";
+        let diagnostics = analyse(source);
+
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].message, "unreachable expression");
+        assert_eq!(labelled(source, &diagnostics[0]), ["2", "return 1"]);
+    }
+
+    #[test]
+    fn code_after_loop_control_is_unreachable() {
+        let source = "fn f do while true do\n    break\n    f()\n    1\nend end";
+        let diagnostics = analyse(source);
+
+        // only the first unreachable expression is reported
+        assert_eq!(diagnostics.len(), 1, "{:?}", diagnostics);
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(labelled(source, &diagnostics[0]), ["f()", "break"]);
+    }
+
+    #[test]
+    fn terminator_at_the_end_of_a_block_is_reachable_code() {
+        let source = "
+            fn f(x: bool) -> i64 do
+                if x do return 1 end
+                while true do
+                    if x do continue end
+                    break
+                end
+                return 2
+            end
+        ";
+        assert!(analyse(source).is_empty(), "{:?}", analyse(source));
+    }
+}
@@ -7,11 +7,22 @@ void print_digit(long number)
     printf("%ld\n", number);
 }
 
+void print_float(double number)
+{
+    printf("%g\n", number);
+}
+
 void ijssel_exit(long number) 
 {
     exit(number);
 }
 
+void ijssel_panic(const char *message, const char *file, long line, long column)
+{
+    fprintf(stderr, "panic at %s:%ld:%ld: %s\n", file, line, column, message);
+    exit(101);
+}
+
 long ijssel_time()
 {
     return time(NULL);